@@ -0,0 +1,120 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde_json;
+use super::{AuditEvent, AuditSink};
+
+const ROTATED_SUFFIX: &'static str = ".1";
+
+/// Appends every event as a line of json to a file
+///
+/// Once the file would grow past `max_bytes` it is renamed by appending `.1` to its name,
+/// replacing any previous rotated file, and a new file is started.
+pub struct FileAuditSink {
+    path: PathBuf,
+    max_bytes: u64,
+    file: Mutex<File>
+}
+
+impl FileAuditSink {
+    /// Opens the audit file, creating it if it does not exist yet
+    pub fn new<P: AsRef<Path>>(path: P, max_bytes: u64) -> io::Result<FileAuditSink> {
+        let path = path.as_ref().to_path_buf();
+        let file = open(&path)?;
+        Ok(FileAuditSink {
+            path: path,
+            max_bytes: max_bytes,
+            file: Mutex::new(file)
+        })
+    }
+
+    /// The path the current file is rotated to
+    pub fn rotated_path(&self) -> PathBuf {
+        let mut rotated: OsString = self.path.clone().into_os_string();
+        rotated.push(ROTATED_SUFFIX);
+        PathBuf::from(rotated)
+    }
+
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let size = file.metadata()?.len();
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            fs::rename(&self.path, self.rotated_path())?;
+            *file = open(&self.path)?;
+        }
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, event: AuditEvent) {
+        let result = serde_json::to_string(&event)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            .and_then(|json| self.write_line(&(json + "\n")));
+        if let Err(e) = result {
+            warn!("Unable to write audit event to {:?}: {}", self.path, e);
+        }
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Read;
+    use super::super::AuditOutcome;
+    use super::super::tests::build_test_event;
+
+    #[test]
+    fn test_writes_json_lines() {
+        let path = test_path("lines");
+        let sink = FileAuditSink::new(&path, 1024 * 1024).unwrap();
+        sink.record(build_test_event(AuditOutcome::Success));
+        sink.record(build_test_event(AuditOutcome::Failure("boom".to_owned())));
+
+        let contents = read(&path);
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].contains("\"outcome\":\"SUCCESS\""));
+        assert!(lines[1].contains("\"outcome\":\"FAILURE\""));
+        cleanup(&sink);
+    }
+
+    #[test]
+    fn test_rotates_when_full() {
+        let path = test_path("rotate");
+        let line_length = serde_json::to_string(&build_test_event(AuditOutcome::Success)).unwrap().len() as u64 + 1;
+        let sink = FileAuditSink::new(&path, line_length * 2).unwrap();
+        for _ in 0..3 {
+            sink.record(build_test_event(AuditOutcome::Success));
+        }
+
+        assert_eq!(2, read(&sink.rotated_path()).lines().count());
+        assert_eq!(1, read(&path).lines().count());
+        cleanup(&sink);
+    }
+
+    fn test_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("rust-eureka-audit-{}.log", name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn read(path: &Path) -> String {
+        let mut contents = String::new();
+        File::open(path).unwrap().read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    fn cleanup(sink: &FileAuditSink) {
+        let _ = fs::remove_file(&sink.path);
+        let _ = fs::remove_file(sink.rotated_path());
+    }
+}
@@ -0,0 +1,244 @@
+//! Audit records of the writes this client performs against the registry
+//!
+//! Every write the [EurekaClient](../struct.EurekaClient.html) makes is reported to its
+//! [AuditSink](trait.AuditSink.html) once the server has answered, whether it succeeded or not.
+//! The default sink discards the events.
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use duration::as_millis;
+use effective_config::redact_url;
+use errors::EurekaClientError;
+
+#[cfg(feature = "audit-file")]
+mod file_sink;
+
+#[cfg(feature = "audit-file")]
+pub use self::file_sink::FileAuditSink;
+
+// Field name constants
+const AUDIT_EVENT: &'static str = "AuditEvent";
+const ACTION: &'static str = "action";
+const TIMESTAMP: &'static str = "timestamp";
+const APP_ID: &'static str = "appId";
+const INSTANCE_ID: &'static str = "instanceId";
+const OUTCOME: &'static str = "outcome";
+const ERROR: &'static str = "error";
+const SERVER_URL: &'static str = "serverUrl";
+const DURATION_MS: &'static str = "durationMs";
+const CONTEXT: &'static str = "context";
+
+const REGISTER: &'static str = "REGISTER";
+const DEREGISTER: &'static str = "DEREGISTER";
+const STATUS_OVERRIDE: &'static str = "STATUS_OVERRIDE";
+const METADATA_UPDATE: &'static str = "METADATA_UPDATE";
+const SUCCESS: &'static str = "SUCCESS";
+const FAILURE: &'static str = "FAILURE";
+
+/// Receives an [AuditEvent](struct.AuditEvent.html) for every write against the registry
+pub trait AuditSink {
+    fn record(&self, event: AuditEvent);
+}
+
+/// The default sink, it discards every event
+#[derive(Debug, Default)]
+pub struct NoopAuditSink;
+
+impl AuditSink for NoopAuditSink {
+    fn record(&self, _event: AuditEvent) {}
+}
+
+/// The kind of write that was performed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    Register,
+    Deregister,
+    StatusOverride,
+    MetadataUpdate
+}
+
+impl AuditAction {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            AuditAction::Register => REGISTER,
+            AuditAction::Deregister => DEREGISTER,
+            AuditAction::StatusOverride => STATUS_OVERRIDE,
+            AuditAction::MetadataUpdate => METADATA_UPDATE
+        }
+    }
+}
+
+/// Whether a write succeeded, failures carry the error that was returned to the caller
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuditOutcome {
+    Success,
+    Failure(String)
+}
+
+/// A single write against the registry
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub action: AuditAction,
+    /// When the write was started, in milliseconds since the unix epoch
+    pub timestamp: u64,
+    pub app_id: String,
+    pub instance_id: String,
+    pub outcome: AuditOutcome,
+    /// The eureka url the write went to, with any user name and password masked
+    pub server_url: String,
+    /// How long the server took to answer
+    pub duration: Duration,
+    /// The context of the client that performed the write, see
+    /// [EurekaClient::set_context](../struct.EurekaClient.html#method.set_context)
+    pub context: BTreeMap<String, String>
+}
+
+impl Serialize for AuditEvent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        let mut s = serializer.serialize_struct(AUDIT_EVENT, 9)?;
+        s.serialize_field(ACTION, self.action.as_str())?;
+        s.serialize_field(TIMESTAMP, &self.timestamp)?;
+        s.serialize_field(APP_ID, &self.app_id)?;
+        s.serialize_field(INSTANCE_ID, &self.instance_id)?;
+
+        match self.outcome {
+            AuditOutcome::Success => s.serialize_field(OUTCOME, SUCCESS)?,
+            AuditOutcome::Failure(ref error) => {
+                s.serialize_field(OUTCOME, FAILURE)?;
+                s.serialize_field(ERROR, error)?;
+            }
+        }
+
+        s.serialize_field(SERVER_URL, &self.server_url)?;
+        s.serialize_field(DURATION_MS, &as_millis(self.duration))?;
+        if !self.context.is_empty() {
+            s.serialize_field(CONTEXT, &self.context)?;
+        }
+        s.end()
+    }
+}
+
+/// A write that has been started but not yet answered
+pub(crate) struct PendingAudit {
+    sink: Rc<AuditSink>,
+    action: AuditAction,
+    timestamp: SystemTime,
+    started: Instant,
+    app_id: String,
+    instance_id: String,
+    server_url: String,
+    context: BTreeMap<String, String>
+}
+
+impl PendingAudit {
+    pub(crate) fn start(sink: Rc<AuditSink>,
+                        action: AuditAction,
+                        app_id: &str,
+                        instance_id: &str,
+                        server_url: &str,
+                        context: &BTreeMap<String, String>) -> PendingAudit {
+        PendingAudit {
+            sink: sink,
+            action: action,
+            timestamp: SystemTime::now(),
+            started: Instant::now(),
+            app_id: app_id.to_owned(),
+            instance_id: instance_id.to_owned(),
+            server_url: redact_url(server_url),
+            context: context.clone()
+        }
+    }
+
+    /// Hands the finished write over to the sink
+    pub(crate) fn finish<T>(self, result: &Result<T, EurekaClientError>) {
+        let outcome = match *result {
+            Ok(_) => AuditOutcome::Success,
+            Err(ref e) => AuditOutcome::Failure(e.to_string())
+        };
+        let timestamp = self.timestamp.duration_since(UNIX_EPOCH)
+            .map(as_millis)
+            .unwrap_or(0);
+        self.sink.record(AuditEvent {
+            action: self.action,
+            timestamp: timestamp,
+            app_id: self.app_id,
+            instance_id: self.instance_id,
+            outcome: outcome,
+            server_url: self.server_url,
+            duration: self.started.elapsed(),
+            context: self.context
+        });
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use serde_json;
+    use std::cell::RefCell;
+
+    struct RecordingSink {
+        events: RefCell<Vec<AuditEvent>>
+    }
+
+    impl AuditSink for RecordingSink {
+        fn record(&self, event: AuditEvent) {
+            self.events.borrow_mut().push(event);
+        }
+    }
+
+    #[test]
+    fn test_success_serialization() {
+        let json = serde_json::to_string(&build_test_event(AuditOutcome::Success)).unwrap();
+        assert_eq!("{\"action\":\"REGISTER\",\"timestamp\":1503442035871,\"appId\":\"MY_APP\",\"instanceId\":\"host1\",\
+                    \"outcome\":\"SUCCESS\",\"serverUrl\":\"http://localhost:8761\",\"durationMs\":1500}", json);
+    }
+
+    #[test]
+    fn test_failure_serialization() {
+        let json = serde_json::to_string(&build_test_event(AuditOutcome::Failure("boom".to_owned()))).unwrap();
+        assert!(json.contains("\"outcome\":\"FAILURE\",\"error\":\"boom\""));
+    }
+
+    #[test]
+    fn test_context_serialization() {
+        let mut event = build_test_event(AuditOutcome::Success);
+        event.context.insert("tenant".to_owned(), "acme".to_owned());
+        let json = serde_json::to_string(&event).unwrap();
+        assert!(json.ends_with("\"durationMs\":1500,\"context\":{\"tenant\":\"acme\"}}"));
+    }
+
+    #[test]
+    fn test_pending_audit_records_outcome() {
+        let sink = Rc::new(RecordingSink { events: RefCell::new(Vec::new()) });
+        let mut context = BTreeMap::new();
+        context.insert("tenant".to_owned(), "acme".to_owned());
+        let pending = PendingAudit::start(sink.clone(), AuditAction::Deregister, "MY_APP", "host1",
+                                          "http://localhost:8761", &context);
+        pending.finish::<()>(&Err(EurekaClientError::NotFound));
+
+        let events = sink.events.borrow();
+        assert_eq!(1, events.len());
+        assert_eq!(AuditAction::Deregister, events[0].action);
+        assert_eq!("MY_APP", events[0].app_id);
+        assert_eq!("host1", events[0].instance_id);
+        assert_eq!(AuditOutcome::Failure(EurekaClientError::NotFound.to_string()), events[0].outcome);
+        assert!(events[0].timestamp > 0);
+        assert_eq!(context, events[0].context);
+    }
+
+    pub fn build_test_event(outcome: AuditOutcome) -> AuditEvent {
+        AuditEvent {
+            action: AuditAction::Register,
+            timestamp: 1503442035871,
+            app_id: "MY_APP".to_owned(),
+            instance_id: "host1".to_owned(),
+            outcome: outcome,
+            server_url: "http://localhost:8761".to_owned(),
+            duration: Duration::from_millis(1500),
+            context: BTreeMap::new()
+        }
+    }
+}
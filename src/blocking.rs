@@ -0,0 +1,74 @@
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Core;
+use hyper::Error as HyperError;
+use errors::EurekaClientError;
+use eureka_client::EurekaClient;
+use response::{Instance, Status};
+use retry::RetryPolicy;
+
+const CLIENT_NAME: &'static str = "rust-eureka-discover";
+const TIMEOUT_SECS: u64 = 5;
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Synchronously fetches the UP instances of an application.
+///
+/// A reactor and client are created for the call and torn down before returning,
+/// so this is meant for small tools rather than long running services. Each attempt
+/// times out after 5 seconds and a request that fails to reach eureka is tried up
+/// to 3 times.
+///
+/// # Arguments
+///
+/// * `eureka_url` - The base url to the eureka cluster
+/// * `app` - The name of the application to discover
+pub fn discover(eureka_url: &str, app: &str) -> Result<Vec<Instance>, EurekaClientError> {
+    debug!("discover: eureka_url={:?}, app={:?}", eureka_url, app);
+    let instances = run(eureka_url, |client| {
+        client.get_application(app).map(|response| response.application.instances)
+    })?;
+    Ok(up(instances))
+}
+
+/// Synchronously fetches the UP instances registered with a vip address, whichever
+/// applications they belong to, see [discover](fn.discover.html).
+///
+/// No instance using the vip address is an empty result rather than an error.
+///
+/// # Arguments
+///
+/// * `eureka_url` - The base url to the eureka cluster
+/// * `vip_address` - The vip address to discover
+pub fn discover_vip(eureka_url: &str, vip_address: &str) -> Result<Vec<Instance>, EurekaClientError> {
+    debug!("discover_vip: eureka_url={:?}, vip_address={:?}", eureka_url, vip_address);
+    let instances = run(eureka_url, |client| {
+        client.get_instances_by_vip(vip_address).map(|response| {
+            response.applications.applications
+                .into_iter()
+                .flat_map(|application| application.instances)
+                .collect()
+        })
+    })?;
+    Ok(up(instances))
+}
+
+// Runs the request `f` makes on a reactor of its own, dropping the reactor and client
+// before returning so no connection outlives the call
+fn run<F, R>(eureka_url: &str, f: F) -> Result<Vec<Instance>, EurekaClientError> where
+    F: FnOnce(&EurekaClient) -> R,
+    R: Future<Item=Vec<Instance>, Error=EurekaClientError> {
+    let mut core = Core::new().map_err(HyperError::from)?;
+    let handle = core.handle();
+    let mut client = EurekaClient::new(&handle, CLIENT_NAME, eureka_url);
+    client.set_timeout(Duration::from_secs(TIMEOUT_SECS));
+    client.set_retry_policy(RetryPolicy { max_attempts: MAX_ATTEMPTS, ..RetryPolicy::default() });
+    let request = f(&client);
+    core.run(request)
+}
+
+fn up(instances: Vec<Instance>) -> Vec<Instance> {
+    instances
+        .into_iter()
+        .filter(|instance| instance.status == Status::Up)
+        .collect()
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+use retry::RetryPolicy;
+
+/// Settings that take precedence over the client's own for the calls made through
+/// [EurekaClient::with_options](struct.EurekaClient.html#method.with_options)
+///
+/// A field left as `None` keeps the client's setting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CallOptions {
+    /// Replaces the client's retry policy, `max_attempts: 1` sends each request once
+    pub retry: Option<RetryPolicy>,
+    /// Replaces the client's timeout for each attempt
+    pub timeout: Option<Duration>
+}
@@ -0,0 +1,308 @@
+//! Credentials sent to Eureka servers that sit behind an authenticating gateway
+//!
+//! The [EurekaClient](../struct.EurekaClient.html) asks its
+//! [CredentialsProvider](trait.CredentialsProvider.html) for credentials before each request and
+//! keeps them until the expiry the provider reported. When a request is answered with a 401 the
+//! credentials are fetched again and the request is retried once. Requests that are answered with
+//! a 401 at the same time share a single fetch.
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Instant;
+use futures::{future, Future};
+use futures::future::Shared;
+use hyper::header::{Authorization, Basic, Bearer, Headers};
+use effective_config::REDACTED;
+use errors::EurekaClientError;
+
+/// Credentials for a single request
+///
+/// Their `Debug` output masks the password and token.
+#[derive(Clone, PartialEq)]
+pub enum Credentials {
+    /// Http basic authentication
+    Basic { username: String, password: String },
+    /// A bearer token, such as an OAuth2 access token
+    Bearer { token: String }
+}
+
+impl Credentials {
+    pub(crate) fn apply(&self, headers: &mut Headers) {
+        match *self {
+            Credentials::Basic { ref username, ref password } => headers.set(Authorization(Basic {
+                username: username.clone(),
+                password: Some(password.clone())
+            })),
+            Credentials::Bearer { ref token } => headers.set(Authorization(Bearer {
+                token: token.clone()
+            }))
+        }
+    }
+}
+
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Credentials::Basic { ref username, .. } =>
+                f.debug_struct("Basic").field("username", username).field("password", &REDACTED).finish(),
+            Credentials::Bearer { .. } => f.debug_struct("Bearer").field("token", &REDACTED).finish()
+        }
+    }
+}
+
+/// Credentials along with when they stop being valid
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuedCredentials {
+    pub credentials: Credentials,
+    /// When the credentials expire, `None` if they never do
+    pub expires_at: Option<Instant>
+}
+
+impl IssuedCredentials {
+    /// Credentials that never expire
+    pub fn new(credentials: Credentials) -> IssuedCredentials {
+        IssuedCredentials {
+            credentials: credentials,
+            expires_at: None
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at.map(|expires_at| expires_at <= Instant::now()).unwrap_or(false)
+    }
+}
+
+/// Supplies the credentials for requests, implement this to refresh expiring tokens
+pub trait CredentialsProvider {
+    fn get(&self) -> Box<Future<Item=IssuedCredentials, Error=EurekaClientError>>;
+}
+
+/// Always provides the same basic credentials
+#[derive(Clone)]
+pub struct StaticBasic {
+    username: String,
+    password: String
+}
+
+impl StaticBasic {
+    pub fn new(username: &str, password: &str) -> StaticBasic {
+        StaticBasic {
+            username: username.to_owned(),
+            password: password.to_owned()
+        }
+    }
+}
+
+impl CredentialsProvider for StaticBasic {
+    fn get(&self) -> Box<Future<Item=IssuedCredentials, Error=EurekaClientError>> {
+        let credentials = Credentials::Basic { username: self.username.clone(), password: self.password.clone() };
+        Box::new(future::ok(IssuedCredentials::new(credentials)))
+    }
+}
+
+impl fmt::Debug for StaticBasic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticBasic").field("username", &self.username).field("password", &REDACTED).finish()
+    }
+}
+
+/// Always provides the same bearer token
+#[derive(Clone)]
+pub struct StaticBearer {
+    token: String
+}
+
+impl StaticBearer {
+    pub fn new(token: &str) -> StaticBearer {
+        StaticBearer {
+            token: token.to_owned()
+        }
+    }
+}
+
+impl CredentialsProvider for StaticBearer {
+    fn get(&self) -> Box<Future<Item=IssuedCredentials, Error=EurekaClientError>> {
+        let credentials = Credentials::Bearer { token: self.token.clone() };
+        Box::new(future::ok(IssuedCredentials::new(credentials)))
+    }
+}
+
+impl fmt::Debug for StaticBearer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StaticBearer").field("token", &REDACTED).finish()
+    }
+}
+
+type Outcome = Rc<Result<Credentials, EurekaClientError>>;
+type Fetch = Shared<Box<Future<Item=Outcome, Error=()>>>;
+
+/// Keeps the last credentials handed out by a provider until they expire or are invalidated, and
+/// merges concurrent fetches from the provider into one
+#[derive(Clone)]
+pub(crate) struct CredentialsCache {
+    provider: Rc<CredentialsProvider>,
+    cached: Rc<RefCell<Option<IssuedCredentials>>>,
+    in_flight: Rc<RefCell<Option<Fetch>>>
+}
+
+impl CredentialsCache {
+    pub(crate) fn new(provider: Rc<CredentialsProvider>) -> CredentialsCache {
+        CredentialsCache {
+            provider: provider,
+            cached: Rc::new(RefCell::new(None)),
+            in_flight: Rc::new(RefCell::new(None))
+        }
+    }
+
+    /// The cached credentials while they are valid, otherwise those of the fetch in flight or of
+    /// a new one
+    pub(crate) fn get(&self) -> Box<Future<Item=Credentials, Error=EurekaClientError>> {
+        if let Some(ref issued) = *self.cached.borrow() {
+            if !issued.is_expired() {
+                return Box::new(future::ok(issued.credentials.clone()));
+            }
+        }
+
+        let existing = self.in_flight.borrow().clone();
+        let fetch = match existing {
+            Some(fetch) => {
+                debug!("Joining the credentials fetch in flight");
+                fetch
+            }
+            None => {
+                let (cached, in_flight) = (self.cached.clone(), self.in_flight.clone());
+                let request: Box<Future<Item=Outcome, Error=()>> = Box::new(self.provider.get().then(move |result| {
+                    *in_flight.borrow_mut() = None;
+                    Ok(Rc::new(result.map(|issued| {
+                        let credentials = issued.credentials.clone();
+                        *cached.borrow_mut() = Some(issued);
+                        credentials
+                    })))
+                }));
+                let fetch = request.shared();
+                *self.in_flight.borrow_mut() = Some(fetch.clone());
+                fetch
+            }
+        };
+
+        Box::new(fetch.then(|outcome| match outcome {
+            Ok(outcome) => match **outcome {
+                Ok(ref credentials) => Ok(credentials.clone()),
+                Err(ref e) => Err(e.duplicate())
+            },
+            Err(_) => Err(EurekaClientError::GenericError("the shared credentials fetch failed".to_owned()))
+        }))
+    }
+
+    /// Credentials to replace `rejected`, which eureka answered with a 401
+    ///
+    /// The cache is only invalidated while it still holds `rejected`, a request that was
+    /// rejected after another one already refreshed the credentials gets the new ones.
+    pub(crate) fn refresh(&self, rejected: &Credentials) -> Box<Future<Item=Credentials, Error=EurekaClientError>> {
+        let stale = match *self.cached.borrow() {
+            Some(ref issued) => issued.credentials == *rejected,
+            None => false
+        };
+        if stale {
+            self.invalidate();
+        }
+        self.get()
+    }
+
+    /// Forgets the cached credentials, the next `get` asks the provider again
+    pub(crate) fn invalidate(&self) {
+        *self.cached.borrow_mut() = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    struct CountingProvider {
+        calls: Rc<Cell<usize>>,
+        ttl: Option<Duration>
+    }
+
+    impl CredentialsProvider for CountingProvider {
+        fn get(&self) -> Box<Future<Item=IssuedCredentials, Error=EurekaClientError>> {
+            self.calls.set(self.calls.get() + 1);
+            Box::new(future::ok(IssuedCredentials {
+                credentials: Credentials::Bearer { token: format!("token{}", self.calls.get()) },
+                expires_at: self.ttl.map(|ttl| Instant::now() + ttl)
+            }))
+        }
+    }
+
+    fn cache(ttl: Option<Duration>) -> (CredentialsCache, Rc<Cell<usize>>) {
+        let calls = Rc::new(Cell::new(0));
+        let provider = CountingProvider { calls: calls.clone(), ttl: ttl };
+        (CredentialsCache::new(Rc::new(provider)), calls)
+    }
+
+    fn bearer(token: &str) -> Credentials {
+        Credentials::Bearer { token: token.to_owned() }
+    }
+
+    #[test]
+    fn test_cached_until_invalidated() {
+        let (cache, calls) = cache(None);
+        assert_eq!(bearer("token1"), cache.get().wait().unwrap());
+        assert_eq!(bearer("token1"), cache.get().wait().unwrap());
+        assert_eq!(1, calls.get());
+
+        cache.invalidate();
+        assert_eq!(bearer("token2"), cache.get().wait().unwrap());
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_concurrent_refreshes_share_one_fetch() {
+        let (cache, calls) = cache(None);
+        let rejected = cache.get().wait().unwrap();
+
+        let first = cache.refresh(&rejected);
+        let second = cache.refresh(&rejected);
+        assert_eq!(bearer("token2"), first.wait().unwrap());
+        assert_eq!(bearer("token2"), second.wait().unwrap());
+        assert_eq!(2, calls.get());
+
+        // rejected again after the refresh, the new credentials are kept
+        assert_eq!(bearer("token2"), cache.refresh(&rejected).wait().unwrap());
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_expired_credentials_are_refreshed() {
+        let (cache, calls) = cache(Some(Duration::from_secs(0)));
+        cache.get().wait().unwrap();
+        cache.get().wait().unwrap();
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_static_providers() {
+        assert_eq!(Credentials::Basic { username: "user".to_owned(), password: "secret".to_owned() },
+                   StaticBasic::new("user", "secret").get().wait().unwrap().credentials);
+        assert_eq!(bearer("abc"), StaticBearer::new("abc").get().wait().unwrap().credentials);
+    }
+
+    #[test]
+    fn test_debug_masks_secrets() {
+        let basic = Credentials::Basic { username: "user".to_owned(), password: "secret".to_owned() };
+        assert_eq!(r#"Basic { username: "user", password: "***" }"#, format!("{:?}", basic));
+        assert_eq!(r#"Bearer { token: "***" }"#, format!("{:?}", bearer("abc")));
+        assert!(!format!("{:?}", StaticBasic::new("user", "secret")).contains("secret"));
+        assert!(!format!("{:?}", StaticBearer::new("abc")).contains("abc"));
+        assert!(!format!("{:?}", IssuedCredentials::new(basic)).contains("secret"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut headers = Headers::new();
+        bearer("abc").apply(&mut headers);
+        assert_eq!(Some("Bearer abc".to_owned()), headers.get::<Authorization<Bearer>>().map(|h| h.to_string()));
+    }
+}
@@ -0,0 +1,348 @@
+//! Checks hand-written registration payloads and registered instances
+//!
+//! [validate_register_payload](fn.validate_register_payload.html) reports everything that
+//! keeps a payload from registering, or that eureka servers are known to trip over, instead of
+//! stopping at the first parse error. [scan_registry](fn.scan_registry.html) finds instances
+//! that registered with such mistakes anyway. Both are plain functions so they can be wired
+//! into other tooling.
+use serde_json::{self, Map, Value};
+use metadata::MetadataExt;
+use request::{strip_url_parts, Instance, InstanceBuilder, RegisterRequest, ValidationError, ACCEPTED_FIELDS, REQUIRED_FIELDS};
+use response::ApplicationsResponse;
+
+const ROOT: &'static str = "$";
+const INSTANCE: &'static str = "instance";
+const HOST_NAME: &'static str = "hostName";
+const APP: &'static str = "app";
+const VIP_ADDRESS: &'static str = "vipAddress";
+const SECURE_VIP_ADDRESS: &'static str = "secureVipAddress";
+const STATUS: &'static str = "status";
+const PORT: &'static str = "port";
+const SECURE_PORT: &'static str = "securePort";
+const PORT_DOLLAR: &'static str = "$";
+const PORT_ENABLED: &'static str = "@enabled";
+const DATA_CENTER_INFO: &'static str = "dataCenterInfo";
+const CLASS: &'static str = "@class";
+const METADATA: &'static str = "metadata";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The payload can not be registered
+    Error,
+    /// The payload can be registered, but not as written or not with every server
+    Warning
+}
+
+/// A single finding, located by a json path such as `$.instance.port`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String
+}
+
+/// Everything found in a payload, in the order it was checked
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiagnosticsReport {
+    pub diagnostics: Vec<Diagnostic>
+}
+
+impl DiagnosticsReport {
+    /// Whether the payload can be registered, it may still have warnings
+    pub fn is_valid(&self) -> bool {
+        self.errors().is_empty()
+    }
+
+    pub fn errors(&self) -> Vec<&Diagnostic> {
+        self.with_severity(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> Vec<&Diagnostic> {
+        self.with_severity(Severity::Warning)
+    }
+
+    fn with_severity(&self, severity: Severity) -> Vec<&Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == severity).collect()
+    }
+
+    fn error<S: Into<String>>(&mut self, path: String, message: S) {
+        self.push(Severity::Error, path, message);
+    }
+
+    fn warning<S: Into<String>>(&mut self, path: String, message: S) {
+        self.push(Severity::Warning, path, message);
+    }
+
+    fn push<S: Into<String>>(&mut self, severity: Severity, path: String, message: S) {
+        self.diagnostics.push(Diagnostic { severity: severity, path: path, message: message.into() });
+    }
+}
+
+/// Checks a register request body, as it would be sent to `POST /v2/apps/{appId}`
+///
+/// The payload is first parsed as strictly as the client parses it. Known server gotchas, such as
+/// flat ports, booleans where eureka expects strings and a missing `@class`, are reported as
+/// warnings and fixed for a lenient parse. Payloads that parse either way are validated against
+/// the default [RegistrationLimits](../request/struct.RegistrationLimits.html) and
+/// [NamingPolicy](../request/struct.NamingPolicy.html).
+pub fn validate_register_payload(json: &str) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport::default();
+    let payload: Value = match serde_json::from_str(json) {
+        Ok(payload) => payload,
+        Err(e) => {
+            report.error(ROOT.to_owned(), format!("not valid json: {}", e));
+            return report;
+        }
+    };
+    let strict = serde_json::from_value::<RegisterRequest>(payload.clone()).err();
+
+    let mut fields = match unwrap_envelope(payload, &mut report) {
+        Some(fields) => fields,
+        None => return report
+    };
+    check_gotchas(&mut fields, &mut report);
+    let missing: Vec<&&str> = REQUIRED_FIELDS.iter().filter(|field| !fields.contains_key(**field)).collect();
+    for field in &missing {
+        report.error(path(&[field]), "required field is missing");
+    }
+    if !missing.is_empty() {
+        return report;
+    }
+
+    let instance: Instance = match serde_json::from_value(Value::Object(fields)) {
+        Ok(instance) => instance,
+        Err(e) => {
+            report.error(path(&[]), e.to_string());
+            return report;
+        }
+    };
+    if let Some(e) = strict {
+        report.warning(path(&[]), format!("only parses once the warnings are fixed: {}", e));
+    }
+    validate(instance, &mut report);
+    report
+}
+
+/// Looks for registered instances whose vip addresses include a url scheme or port
+///
+/// Eureka stores such vip addresses as they are, so lookups by the vip address silently miss
+/// the instance. Every offender is a warning located by its position in the registry, as in
+/// `$.applications.application[0].instance[2].vipAddress`.
+pub fn scan_registry(registry: &ApplicationsResponse) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport::default();
+    for (a, app) in registry.applications.applications.iter().enumerate() {
+        for (i, instance) in app.instances.iter().enumerate() {
+            for &(field, vip_address) in &[(VIP_ADDRESS, &instance.vip_address), (SECURE_VIP_ADDRESS, &instance.secure_vip_address)] {
+                if let Some(repaired) = strip_url_parts(vip_address) {
+                    warn!("{} of {} in {} includes a scheme or port: {:?}", field, instance.id(), app.name, vip_address);
+                    report.warning(format!("{}.applications.application[{}].instance[{}].{}", ROOT, a, i, field),
+                                   format!("{:?} includes a scheme or port, lookups by {:?} miss this instance",
+                                           vip_address, repaired));
+                }
+            }
+        }
+    }
+    report
+}
+
+fn unwrap_envelope(payload: Value, report: &mut DiagnosticsReport) -> Option<Map<String, Value>> {
+    let mut envelope = match payload {
+        Value::Object(envelope) => envelope,
+        _ => {
+            report.error(ROOT.to_owned(), "expected an object with an instance field");
+            return None;
+        }
+    };
+    match envelope.remove(INSTANCE) {
+        Some(Value::Object(fields)) => {
+            for name in envelope.keys() {
+                report.warning(format!("{}.{}", ROOT, name), "only the instance field is sent");
+            }
+            Some(fields)
+        }
+        Some(_) => {
+            report.error(path(&[]), "expected an object");
+            None
+        }
+        None if envelope.contains_key(HOST_NAME) => {
+            report.warning(ROOT.to_owned(), "the instance is not wrapped in an instance field");
+            Some(envelope)
+        }
+        None => {
+            report.error(path(&[]), "required field is missing");
+            None
+        }
+    }
+}
+
+// Reports and fixes what the strict parse or eureka servers reject but is clear in intent
+fn check_gotchas(fields: &mut Map<String, Value>, report: &mut DiagnosticsReport) {
+    let unknown: Vec<String> = fields.keys().filter(|name| !ACCEPTED_FIELDS.contains(&name.as_str())).cloned().collect();
+    for name in unknown {
+        report.warning(path(&[&name]), "not a registration field, it is dropped");
+        fields.remove(&name);
+    }
+
+    for name in &[PORT, SECURE_PORT] {
+        let fixed = match fields.get_mut(*name) {
+            Some(port @ &mut Value::Number(_)) | Some(port @ &mut Value::String(_)) => {
+                report.warning(path(&[name]), "eureka expects an object with \"$\" and \"@enabled\"");
+                let mut object = Map::new();
+                object.insert(PORT_DOLLAR.to_owned(), port.clone());
+                object.insert(PORT_ENABLED.to_owned(), Value::String("true".to_owned()));
+                Some(Value::Object(object))
+            }
+            Some(&mut Value::Object(ref mut object)) => {
+                match object.get(PORT_ENABLED).cloned() {
+                    Some(Value::Bool(enabled)) => {
+                        report.warning(path(&[name, PORT_ENABLED]), "eureka expects the string \"true\" or \"false\"");
+                        object.insert(PORT_ENABLED.to_owned(), Value::String(enabled.to_string()));
+                    }
+                    None => {
+                        report.warning(path(&[name, PORT_ENABLED]), "missing, the port is taken as enabled");
+                        object.insert(PORT_ENABLED.to_owned(), Value::String("true".to_owned()));
+                    }
+                    _ => ()
+                }
+                None
+            }
+            _ => None
+        };
+        if let Some(port) = fixed {
+            fields.insert(name.to_string(), port);
+        }
+    }
+
+    if let Some(&Value::Object(ref data_center_info)) = fields.get(DATA_CENTER_INFO) {
+        if !data_center_info.contains_key(CLASS) {
+            report.warning(path(&[DATA_CENTER_INFO, CLASS]), "missing, Netflix servers need it to read the data center");
+        }
+    }
+}
+
+fn validate(instance: Instance, report: &mut DiagnosticsReport) {
+    for keys in instance.metadata.case_conflicts() {
+        report.warning(path(&[METADATA]), format!("keys {:?} only differ by case", keys));
+    }
+    let vip_address = instance.vip_address.clone();
+    let error = match InstanceBuilder::from_instance(instance).build() {
+        Ok(_) => return,
+        Err(error) => error
+    };
+    let at = match error {
+        ValidationError::MetadataValueTooLong { ref key, .. } => path(&[METADATA, key]),
+        ValidationError::MetadataTooLarge { .. } => path(&[METADATA]),
+        ValidationError::BodyTooLarge { .. } => ROOT.to_owned(),
+        ValidationError::InvalidVipAddress { ref value } if *value == vip_address => path(&[VIP_ADDRESS]),
+        ValidationError::InvalidVipAddress { .. } => path(&[SECURE_VIP_ADDRESS]),
+        ValidationError::VipAddressWithUrlParts { ref value, .. } if *value == vip_address => path(&[VIP_ADDRESS]),
+        ValidationError::VipAddressWithUrlParts { .. } => path(&[SECURE_VIP_ADDRESS]),
+        ValidationError::NameCollision { .. } => path(&[APP]),
+        ValidationError::UnknownStatus => path(&[STATUS])
+    };
+    report.error(at, error.to_string());
+}
+
+// the path of a field of the instance
+fn path(fields: &[&str]) -> String {
+    let mut path = format!("{}.{}", ROOT, INSTANCE);
+    for field in fields {
+        path.push('.');
+        path.push_str(field);
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(instance: &str) -> String {
+        format!(r#"{{"instance":{{"hostName":"orders01","app":"ORDERS","ipAddr":"10.0.0.1","vipAddress":"orders",
+                    "secureVipAddress":"orders","status":"UP","homePageUrl":"","statusPageUrl":"","healthCheckUrl":"",
+                    "dataCenterInfo":{{"@class":"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo","name":"MyOwn"}}{}}}}}"#,
+                instance)
+    }
+
+    fn findings(report: &DiagnosticsReport) -> Vec<(Severity, &str)> {
+        report.diagnostics.iter().map(|d| (d.severity, d.path.as_str())).collect()
+    }
+
+    #[test]
+    fn test_valid_payload() {
+        let report = validate_register_payload(&payload(r#","port":{"$":"8080","@enabled":"true"}"#));
+        assert_eq!(DiagnosticsReport::default(), report);
+        assert!(report.is_valid());
+    }
+
+    #[test]
+    fn test_broken_payloads() {
+        use self::Severity::{Error, Warning};
+
+        let long_value = "x".repeat(17 * 1024);
+        let wrapped = payload("");
+        let unwrapped = wrapped["{\"instance\":".len()..wrapped.len() - 1].to_owned();
+        let table: Vec<(String, Vec<(Severity, &str)>)> = vec![
+            ("{\"instance\":".to_owned(), vec![(Error, "$")]),
+            ("[]".to_owned(), vec![(Error, "$")]),
+            ("{}".to_owned(), vec![(Error, "$.instance")]),
+            (payload(r#","port":8080"#), vec![(Warning, "$.instance.port"), (Warning, "$.instance")]),
+            (payload(r#","securePort":"8443""#), vec![(Warning, "$.instance.securePort"), (Warning, "$.instance")]),
+            (payload(r#","port":{"$":8080,"@enabled":true}"#), vec![(Warning, "$.instance.port.@enabled"), (Warning, "$.instance")]),
+            (payload(r#","port":{"$":8080}"#), vec![(Warning, "$.instance.port.@enabled"), (Warning, "$.instance")]),
+            (payload(r#","countryId":1"#), vec![(Warning, "$.instance.countryId")]),
+            (payload(r#","metadata":{"zone":"a","Zone":"b"}"#), vec![(Warning, "$.instance.metadata")]),
+            (payload(&format!(r#","metadata":{{"blob":"{}"}}"#, long_value)), vec![(Error, "$.instance.metadata.blob")]),
+            (payload(r#","port":{"$":"http","@enabled":"true"}"#), vec![(Error, "$.instance")]),
+            (payload("").replace(r#""status":"UP""#, r#""status":"UNKNOWN""#), vec![(Error, "$.instance.status")]),
+            (payload("").replace(r#""status":"UP","#, ""), vec![(Error, "$.instance.status")]),
+            (payload("").replace(r#""hostName":"orders01","#, "").replace(r#""app":"ORDERS","#, ""),
+             vec![(Error, "$.instance.hostName"), (Error, "$.instance.app")]),
+            (payload("").replace(r#""@class":"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo","#, ""),
+             vec![(Warning, "$.instance.dataCenterInfo.@class")]),
+            (unwrapped, vec![(Warning, "$"), (Warning, "$.instance")]),
+            (payload("").replace(r#"{"instance":{"#, r#"{"tenantId":"acme","instance":{"#),
+             vec![(Warning, "$.tenantId"), (Warning, "$.instance")])
+        ];
+        for (json, expected) in table {
+            let report = validate_register_payload(&json);
+            assert_eq!(expected, findings(&report), "{}\n{:#?}", json, report);
+        }
+    }
+
+    #[test]
+    fn test_vip_address_with_url_parts() {
+        let json = payload("").replace(r#""secureVipAddress":"orders""#, r#""secureVipAddress":"https://orders:8443""#);
+        let report = validate_register_payload(&json);
+        assert_eq!(vec![(Severity::Error, "$.instance.secureVipAddress")], findings(&report));
+    }
+
+    #[test]
+    fn test_scan_registry() {
+        use response::{Application, Applications};
+        use response::instance::tests::build_test_instance;
+
+        let mut offender = build_test_instance();
+        offender.vip_address = "http://orders:8080".to_owned();
+        let registry = ApplicationsResponse::new(Applications {
+            versions_delta: 1,
+            apps_hashcode: String::new(),
+            applications: vec![Application { name: "ORDERS".to_owned(), instances: vec![build_test_instance(), offender] }]
+        });
+
+        let report = scan_registry(&registry);
+        assert_eq!(vec![(Severity::Warning, "$.applications.application[0].instance[1].vipAddress")], findings(&report));
+        assert!(report.is_valid());
+        assert!(report.warnings()[0].message.contains("\"orders\""));
+    }
+
+    #[test]
+    fn test_messages_explain_the_problem() {
+        let report = validate_register_payload(&payload(r#","port":8080"#));
+        assert!(report.is_valid());
+        assert!(report.warnings()[0].message.contains("\"$\" and \"@enabled\""));
+        let report = validate_register_payload(&payload("").replace(r#""status":"UP","#, ""));
+        assert!(!report.is_valid());
+        assert_eq!("required field is missing", report.errors()[0].message);
+    }
+}
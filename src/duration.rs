@@ -0,0 +1,18 @@
+use std::time::Duration;
+
+/// The whole milliseconds in `duration`, as eureka and the audit records count time
+pub(crate) fn as_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_as_millis() {
+        assert_eq!(0, as_millis(Duration::from_secs(0)));
+        assert_eq!(1500, as_millis(Duration::from_millis(1500)));
+        assert_eq!(2001, as_millis(Duration::new(2, 1_999_999)));
+    }
+}
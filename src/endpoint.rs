@@ -0,0 +1,180 @@
+use url::form_urlencoded::byte_serialize;
+use url::percent_encoding::{utf8_percent_encode, EncodeSet};
+use keys::{APPS_PATH, APPS_DELTA_PATH, APPS_IN_REGIONS_PATH, APP_PATH, INSTANCE_PATH, STATUS_OVERRIDE_PATH, METADATA_PATH, INSTANCE_BY_ID_PATH,
+           VIP_PATH, SVIP_PATH};
+
+const APP_ID: &'static str = "{appId}";
+const INSTANCE_ID: &'static str = "{instanceId}";
+const VIP_ADDRESS: &'static str = "{vipAddress}";
+const SVIP_ADDRESS: &'static str = "{svipAddress}";
+
+/// The Eureka REST endpoints used by the [EurekaClient](struct.EurekaClient.html)
+///
+/// Paths are rendered relative to the api prefix, `/v2` unless the client was given another
+/// one with [set_api_prefix](struct.EurekaClient.html#method.set_api_prefix).
+///
+/// See: https://github.com/Netflix/eureka/wiki/Eureka-REST-operations
+#[derive(Debug, PartialEq)]
+pub enum Endpoint<'a> {
+    /// All registered applications
+    Applications,
+    /// The changes to the registry since the last fetch
+    ApplicationsDelta,
+    /// All registered applications across regions, queried with a trailing slash the way the
+    /// Netflix client does as some servers only serve the regions query there
+    ApplicationsInRegions,
+    /// A single application, used both to query and to register instances
+    Application(&'a str),
+    /// A single instance of an application, used to deregister it and to send heartbeats
+    Instance(&'a str, &'a str),
+    /// The status override of an instance
+    StatusOverride(&'a str, &'a str),
+    /// The metadata of an instance
+    Metadata(&'a str, &'a str),
+    /// An instance looked up by its id alone, whatever application it belongs to
+    InstanceById(&'a str),
+    /// The instances registered with a vip address, across applications
+    Vip(&'a str),
+    /// The instances registered with a secure vip address, across applications
+    SecureVip(&'a str)
+}
+
+impl<'a> Endpoint<'a> {
+    /// Renders the path of this endpoint, percent-encoding every parameter as a path segment
+    pub fn path(&self) -> String {
+        match *self {
+            Endpoint::Applications => APPS_PATH.to_owned(),
+            Endpoint::ApplicationsDelta => APPS_DELTA_PATH.to_owned(),
+            Endpoint::ApplicationsInRegions => APPS_IN_REGIONS_PATH.to_owned(),
+            Endpoint::Application(app_id) => render(APP_PATH, &[(APP_ID, app_id)]),
+            Endpoint::Instance(app_id, instance_id) => render(INSTANCE_PATH, &[(APP_ID, app_id), (INSTANCE_ID, instance_id)]),
+            Endpoint::StatusOverride(app_id, instance_id) =>
+                render(STATUS_OVERRIDE_PATH, &[(APP_ID, app_id), (INSTANCE_ID, instance_id)]),
+            Endpoint::Metadata(app_id, instance_id) => render(METADATA_PATH, &[(APP_ID, app_id), (INSTANCE_ID, instance_id)]),
+            Endpoint::InstanceById(instance_id) => render(INSTANCE_BY_ID_PATH, &[(INSTANCE_ID, instance_id)]),
+            Endpoint::Vip(vip_address) => render(VIP_PATH, &[(VIP_ADDRESS, vip_address)]),
+            Endpoint::SecureVip(svip_address) => render(SVIP_PATH, &[(SVIP_ADDRESS, svip_address)])
+        }
+    }
+
+    /// Renders the path of this endpoint with query parameters, form-encoding their names and values
+    pub fn path_with_query(&self, params: &[(&str, &str)]) -> String {
+        if params.is_empty() {
+            return self.path();
+        }
+        let query: Vec<String> = params.iter()
+            .map(|&(name, value)| format!("{}={}", encode_query(name), encode_query(value)))
+            .collect();
+        format!("{}?{}", self.path(), query.join("&"))
+    }
+
+    /// Renders the path of this endpoint with a single parameter holding a comma separated
+    /// list, every value is form-encoded on its own so the separating commas stay readable
+    pub fn path_with_list(&self, name: &str, values: &[&str]) -> String {
+        if values.is_empty() {
+            return self.path();
+        }
+        let values: Vec<String> = values.iter().map(|value| encode_query(value)).collect();
+        format!("{}?{}={}", self.path(), encode_query(name), values.join(","))
+    }
+}
+
+fn render(template: &str, params: &[(&str, &str)]) -> String {
+    params.iter().fold(template.to_owned(), |path, &(placeholder, value)| {
+        path.replace(placeholder, &encode_segment(value))
+    })
+}
+
+// Everything RFC 3986 does not allow in a path segment as it is. The unreserved characters, the
+// sub-delims, ':' and '@' are left alone, so the ids eureka builds out of host names and ports
+// stay readable. A '%' is always encoded, ids are never taken to be encoded already.
+#[derive(Clone)]
+struct SegmentEncodeSet;
+
+impl EncodeSet for SegmentEncodeSet {
+    fn contains(&self, byte: u8) -> bool {
+        !(byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte))
+    }
+}
+
+fn encode_segment(segment: &str) -> String {
+    utf8_percent_encode(segment, SegmentEncodeSet).to_string()
+}
+
+fn encode_query(component: &str) -> String {
+    byte_serialize(component.as_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_applications_path() {
+        assert_eq!("/apps", Endpoint::Applications.path());
+        assert_eq!("/apps/delta", Endpoint::ApplicationsDelta.path());
+        assert_eq!("/apps/", Endpoint::ApplicationsInRegions.path());
+    }
+
+    #[test]
+    fn test_application_path() {
+        assert_eq!("/apps/MY_APP", Endpoint::Application("MY_APP").path());
+    }
+
+    #[test]
+    fn test_application_path_is_encoded() {
+        assert_eq!("/apps/my%20app%2Fv2", Endpoint::Application("my app/v2").path());
+    }
+
+    #[test]
+    fn test_status_override_path_with_query() {
+        assert_eq!("/apps/MY_APP/host1/status?value=OUT_OF_SERVICE",
+                   Endpoint::StatusOverride("MY_APP", "host1").path_with_query(&[("value", "OUT_OF_SERVICE")]));
+        assert_eq!("/apps/MY_APP/host1/status?value=a+b%26c%3D",
+                   Endpoint::StatusOverride("MY_APP", "host1").path_with_query(&[("value", "a b&c=")]));
+        assert_eq!("/apps/MY_APP/host1/status", Endpoint::StatusOverride("MY_APP", "host1").path_with_query(&[]));
+    }
+
+    #[test]
+    fn test_metadata_query_names_are_encoded() {
+        assert_eq!("/apps/MY_APP/host1/metadata?deploy%2Ftag=v+1%3D2&zone=primary",
+                   Endpoint::Metadata("MY_APP", "host1").path_with_query(&[("deploy/tag", "v 1=2"), ("zone", "primary")]));
+    }
+
+    #[test]
+    fn test_path_with_list() {
+        assert_eq!("/apps/?regions=us-east-1,eu-west-1",
+                   Endpoint::ApplicationsInRegions.path_with_list("regions", &["us-east-1", "eu-west-1"]));
+        assert_eq!("/apps?regions=a%2Cb,c+d", Endpoint::Applications.path_with_list("regions", &["a,b", "c d"]));
+        assert_eq!("/apps", Endpoint::Applications.path_with_list("regions", &[]));
+    }
+
+    #[test]
+    fn test_instance_by_id_path() {
+        assert_eq!("/instances/ip-10-0-0-1.ec2.internal:orders:8080", Endpoint::InstanceById("ip-10-0-0-1.ec2.internal:orders:8080").path());
+        assert_eq!("/instances/a%2Fb%3Fc%23d", Endpoint::InstanceById("a/b?c#d").path());
+    }
+
+    #[test]
+    fn test_vip_paths() {
+        assert_eq!("/vips/orders.internal", Endpoint::Vip("orders.internal").path());
+        assert_eq!("/svips/orders.internal", Endpoint::SecureVip("orders.internal").path());
+        assert_eq!("/vips/orders%2Fv2", Endpoint::Vip("orders/v2").path());
+    }
+
+    #[test]
+    fn test_instance_path() {
+        assert_eq!("/apps/MY_APP/host%2F1", Endpoint::Instance("MY_APP", "host/1").path());
+        assert_eq!("/apps/my%20app/i-abc%2Fweird%23frag", Endpoint::Instance("my app", "i-abc/weird#frag").path());
+    }
+
+    #[test]
+    fn test_segments_only_encode_what_they_must() {
+        assert_eq!("/instances/%5B::1%5D:8080", Endpoint::InstanceById("[::1]:8080").path());
+        assert_eq!("/instances/a%7Cb%5Ec%5Cd%22e%60", Endpoint::InstanceById("a|b^c\\d\"e`").path());
+        assert_eq!("/instances/-._~!$&'()*+,;=:@", Endpoint::InstanceById("-._~!$&'()*+,;=:@").path());
+        // ids are raw, a percent sign is not an escape
+        assert_eq!("/instances/x%2520y", Endpoint::InstanceById("x%20y").path());
+        assert_eq!("/instances/%C3%BCn%C3%AF", Endpoint::InstanceById("ünï").path());
+    }
+}
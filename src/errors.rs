@@ -2,56 +2,456 @@ use std::error::Error;
 use std::fmt::Display;
 use std::fmt;
 use std::convert::From;
+use std::io;
+use std::time::Duration;
+use serde::de::Error as DeError;
 use hyper::error::Error as HyperError;
 use serde_json::error::Error as ParserError;
 use hyper::error::UriError;
+use hyper::{Method, StatusCode};
+use hyper::mime::{self, Mime};
+use operation::Operation;
 
 use self::EurekaClientError::*;
 
 /// Errors that can be returned by the [EurekaClient](struct.EurekaClient.html)
+///
+/// Errors of a request that was sent come wrapped in `Request`, naming the request and the
+/// response to it, match on [inner](#method.inner) to tell them apart.
 #[derive(Debug)]
 pub enum EurekaClientError {
     /// An underlying error occurred with the Hyper http client
     ClientError(HyperError),
-    /// An error occurred parsing a response from the server
+    /// A json error that did not come from a response body, such as one serializing a request
     JsonError(ParserError),
     /// A generic error that was no otherwise typed occurred
     GenericError(String),
     /// The Uri of the Eureka server was invalid
     InvalidUri(UriError),
-    /// An server error occurred with Eureka
-    InternalServerError,
+    /// The base url of the eureka cluster can not be used, with the reason
+    InvalidBaseUrl(String),
+    /// A client was built from missing or contradictory settings, with the reason
+    InvalidConfiguration(String),
+    /// An server error occurred with Eureka, returned for every 5xx response, with its status
+    InternalServerError(u16),
     /// Request parameters sent to Eureka were invalid
     BadRequest,
     /// The specified resource does not exist in eureka, such as an invalid application name
-    NotFound
+    NotFound,
+    /// Eureka does not know the instance a heartbeat was sent for, it has to register again
+    InstanceNotFound,
+    /// Eureka requires credentials that were missing or invalid (401)
+    Unauthorized,
+    /// The credentials sent to Eureka do not allow the request (403)
+    Forbidden,
+    /// The endpoint does not support the http method used (405)
+    MethodNotAllowed,
+    /// Eureka can not produce a response in the requested format (406)
+    NotAcceptable,
+    /// The request conflicts with the current state of the registry (409)
+    Conflict,
+    /// Eureka is throttling this client (429)
+    TooManyRequests,
+    /// Any other unsuccessful status, with the start of the response body
+    UnexpectedStatus(u16, String),
+    /// The connection closed before the whole response body arrived, with the number of bytes received
+    TruncatedResponse(usize),
+    /// Eureka did not answer with a whole response within the client's timeout or before the
+    /// deadline of the call, with how long the operation waited
+    Timeout { elapsed: Duration, operation: Operation },
+    /// The response body could not be decoded from its Content-Encoding, with the reason
+    DecodeError(String),
+    /// Eureka could not be connected to, the connection was refused or the address is not
+    /// reachable, so nothing was sent
+    Connection(io::Error),
+    /// The host name of eureka could not be resolved, so nothing was sent
+    Dns(io::Error),
+    /// A response body is not the json expected, with the parser's message, the start of the
+    /// body, its Content-Type if it had one and the parser error itself as the source
+    Deserialize { message: String, body_snippet: String, content_type: Option<String>, cause: ParserError },
+    /// A successful response is not json at all, such as the error page of a load balancer in
+    /// front of eureka, with its Content-Type and the start of the body
+    NonJsonResponse { content_type: String, body_snippet: String },
+    /// Every attempt the retry policy allowed failed, with the error of the last one
+    RetriesExhausted { attempts: u32, last: Box<EurekaClientError> },
+    /// The operation is not among the client's allowed operations, nothing was sent
+    OperationDisabled { operation: Operation },
+    /// Any of the errors above, with the request that failed and the response to it
+    Request { context: RequestContext, error: Box<EurekaClientError> }
+}
+
+/// The request an error was returned for, see
+/// [EurekaClientError::request](enum.EurekaClientError.html#method.request)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    pub method: String,
+    /// The full uri the request was sent to, credentials are never part of it
+    pub uri: String,
+    /// The status eureka answered with, none when no response arrived
+    pub status: Option<u16>,
+    /// The start of the response body, at most 1KB of it
+    pub body: String
+}
+
+impl RequestContext {
+    pub(crate) fn new(method: &Method, uri: &str, status: Option<StatusCode>, body: &[u8]) -> RequestContext {
+        RequestContext {
+            method: method.to_string(),
+            uri: uri.to_owned(),
+            status: status.map(u16::from),
+            body: error_context(body)
+        }
+    }
+}
+
+/// Whose fault an error is, used to split error budgets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// The request this client sent was rejected (4xx) or could not be built
+    Client,
+    /// Eureka failed to handle a valid request (5xx and other unexpected responses)
+    Server,
+    /// Eureka could not be reached or the connection failed
+    Network,
+    /// Something went wrong inside this client
+    Internal
+}
+
+impl EurekaClientError {
+    /// The category of this error
+    pub fn category(&self) -> ErrorCategory {
+        match *self {
+            ClientError(_) | Connection(_) | Dns(_) | TruncatedResponse(_) | Timeout { .. } => ErrorCategory::Network,
+            JsonError(_) | GenericError(_) => ErrorCategory::Internal,
+            InvalidUri(_) | InvalidBaseUrl(_) | InvalidConfiguration(_) | OperationDisabled { .. } => ErrorCategory::Client,
+            InternalServerError(_) | DecodeError(_) | Deserialize { .. } | NonJsonResponse { .. } => ErrorCategory::Server,
+            RetriesExhausted { ref last, .. } => last.category(),
+            Request { ref error, .. } => error.category(),
+            BadRequest | NotFound | InstanceNotFound | Unauthorized | Forbidden | MethodNotAllowed
+            | NotAcceptable | Conflict | TooManyRequests => ErrorCategory::Client,
+            UnexpectedStatus(400..=499, _) => ErrorCategory::Client,
+            UnexpectedStatus(..) => ErrorCategory::Server
+        }
+    }
+
+    /// Whether the failure is likely transient, so the same request may succeed later
+    ///
+    /// True for network failures, timeouts, 5xx responses and throttling. Garbled responses,
+    /// rejected requests and configuration errors fail the same way every time, as do 501 (Not
+    /// Implemented) and 505 (HTTP Version Not Supported). This is broader than what a
+    /// [RetryPolicy](../struct.RetryPolicy.html) sends again by itself, it only resends
+    /// requests that got no answer.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            RetriesExhausted { ref last, .. } => last.is_retryable(),
+            Request { ref error, .. } => error.is_retryable(),
+            InternalServerError(status) => status != 501 && status != 505,
+            TooManyRequests => true,
+            ref other => other.category() == ErrorCategory::Network
+        }
+    }
+
+    /// The status eureka answered with, of the last attempt when every attempt failed, none
+    /// when no response arrived
+    pub fn status(&self) -> Option<StatusCode> {
+        let code = match *self {
+            Request { ref context, ref error } => return context.status.and_then(|code| StatusCode::try_from(code).ok())
+                .or_else(|| error.status()),
+            RetriesExhausted { ref last, .. } => return last.status(),
+            BadRequest => 400,
+            Unauthorized => 401,
+            Forbidden => 403,
+            NotFound => 404,
+            MethodNotAllowed => 405,
+            NotAcceptable => 406,
+            Conflict => 409,
+            TooManyRequests => 429,
+            InternalServerError(code) | UnexpectedStatus(code, _) => code,
+            _ => return None
+        };
+        StatusCode::try_from(code).ok()
+    }
+
+    /// Whether the request failed before anything was sent, because eureka could not be reached
+    pub fn is_connect_error(&self) -> bool {
+        match *self {
+            Connection(_) | Dns(_) => true,
+            Request { ref error, .. } => error.is_connect_error(),
+            _ => false
+        }
+    }
+
+    /// The request this error was returned for, of the last attempt when every attempt failed
+    ///
+    /// None for errors that happened before anything was sent, such as an invalid base url.
+    pub fn request(&self) -> Option<&RequestContext> {
+        match *self {
+            Request { ref context, .. } => Some(context),
+            RetriesExhausted { ref last, .. } => last.request(),
+            _ => None
+        }
+    }
+
+    /// The error without the request it was returned for, to match on
+    ///
+    /// ```
+    /// # extern crate rust_eureka;
+    /// # use rust_eureka::errors::EurekaClientError;
+    /// fn is_missing(error: &EurekaClientError) -> bool {
+    ///     match *error.inner() {
+    ///         EurekaClientError::NotFound => true,
+    ///         _ => false
+    ///     }
+    /// }
+    /// # fn main() {}
+    /// ```
+    pub fn inner(&self) -> &EurekaClientError {
+        match *self {
+            Request { ref error, .. } => error.inner(),
+            ref other => other
+        }
+    }
+
+    /// Like [inner](#method.inner), taking the error
+    pub fn into_inner(self) -> EurekaClientError {
+        match self {
+            Request { error, .. } => error.into_inner(),
+            other => other
+        }
+    }
+
+    // Attaches the request an error was returned for, an error keeps the first one attached
+    pub(crate) fn in_request(self, context: RequestContext) -> EurekaClientError {
+        match self {
+            error @ Request { .. } => error,
+            error => Request { context: context, error: Box::new(error) }
+        }
+    }
+}
+
+impl EurekaClientError {
+    // A copy for callers sharing the outcome of one request. The errors of other libraries can
+    // not be cloned, they are rebuilt from their message keeping the category.
+    pub(crate) fn duplicate(&self) -> EurekaClientError {
+        match *self {
+            ClientError(HyperError::Io(ref e)) => ClientError(HyperError::Io(io::Error::new(e.kind(), e.to_string()))),
+            ClientError(ref e) => ClientError(HyperError::Io(io::Error::new(io::ErrorKind::Other, e.to_string()))),
+            JsonError(ref e) => JsonError(ParserError::custom(e.to_string())),
+            GenericError(ref message) => GenericError(message.clone()),
+            InvalidUri(ref e) => GenericError(e.to_string()),
+            InvalidBaseUrl(ref reason) => InvalidBaseUrl(reason.clone()),
+            InvalidConfiguration(ref reason) => InvalidConfiguration(reason.clone()),
+            InternalServerError(status) => InternalServerError(status),
+            BadRequest => BadRequest,
+            NotFound => NotFound,
+            InstanceNotFound => InstanceNotFound,
+            Unauthorized => Unauthorized,
+            Forbidden => Forbidden,
+            MethodNotAllowed => MethodNotAllowed,
+            NotAcceptable => NotAcceptable,
+            Conflict => Conflict,
+            TooManyRequests => TooManyRequests,
+            UnexpectedStatus(status, ref body) => UnexpectedStatus(status, body.clone()),
+            TruncatedResponse(received) => TruncatedResponse(received),
+            Timeout { elapsed, operation } => Timeout { elapsed: elapsed, operation: operation },
+            DecodeError(ref reason) => DecodeError(reason.clone()),
+            Connection(ref e) => Connection(io::Error::new(e.kind(), e.to_string())),
+            Dns(ref e) => Dns(io::Error::new(e.kind(), e.to_string())),
+            Deserialize { ref message, ref body_snippet, ref content_type, .. } => Deserialize {
+                message: message.clone(),
+                body_snippet: body_snippet.clone(),
+                content_type: content_type.clone(),
+                cause: ParserError::custom(message)
+            },
+            NonJsonResponse { ref content_type, ref body_snippet } => NonJsonResponse {
+                content_type: content_type.clone(),
+                body_snippet: body_snippet.clone()
+            },
+            RetriesExhausted { attempts, ref last } => RetriesExhausted { attempts: attempts, last: Box::new(last.duplicate()) },
+            OperationDisabled { operation } => OperationDisabled { operation: operation },
+            Request { ref context, ref error } => Request { context: context.clone(), error: Box::new(error.duplicate()) }
+        }
+    }
+}
+
+/// The number of bytes of a response body kept as context for an error
+const MAX_ERROR_BODY_LENGTH: usize = 1024;
+
+/// Maps the status of a response to the error returned for it, every endpoint goes through here
+///
+/// | Status          | Error                 |
+/// |-----------------|-----------------------|
+/// | 2xx             | none                  |
+/// | 400             | `BadRequest`          |
+/// | 401             | `Unauthorized`        |
+/// | 403             | `Forbidden`           |
+/// | 404             | `NotFound`            |
+/// | 405             | `MethodNotAllowed`    |
+/// | 406             | `NotAcceptable`       |
+/// | 409             | `Conflict`            |
+/// | 429             | `TooManyRequests`     |
+/// | 5xx             | `InternalServerError` |
+/// | everything else | `UnexpectedStatus`    |
+///
+/// `InternalServerError` and `UnexpectedStatus` keep the status they stand for.
+pub(crate) fn check_status(status: StatusCode, body: &[u8]) -> Result<(), EurekaClientError> {
+    match status_error(status, body) {
+        Some(error) => {
+            warn!("Eureka responded with {}: {}", status, error_context(body));
+            Err(error)
+        }
+        None => Ok(())
+    }
+}
+
+// The error check_status fails with, without logging it
+pub(crate) fn status_error(status: StatusCode, body: &[u8]) -> Option<EurekaClientError> {
+    if status.is_success() {
+        return None;
+    }
+
+    let context = error_context(body);
+    let code = u16::from(status);
+    let error = match code {
+        400 => BadRequest,
+        401 => Unauthorized,
+        403 => Forbidden,
+        404 => NotFound,
+        405 => MethodNotAllowed,
+        406 => NotAcceptable,
+        409 => Conflict,
+        429 => TooManyRequests,
+        500..=599 => InternalServerError(code),
+        _ => UnexpectedStatus(code, context)
+    };
+    Some(error)
+}
+
+/// Like [check_status](fn.check_status.html) for endpoints whose contract is 204 No Content,
+/// 200 is accepted from lenient servers but any other 2xx is `UnexpectedStatus`
+pub(crate) fn check_no_content(status: StatusCode, body: &[u8]) -> Result<(), EurekaClientError> {
+    check_status(status, body)?;
+    match status {
+        StatusCode::NoContent | StatusCode::Ok => Ok(()),
+        other => {
+            let context = error_context(body);
+            warn!("Eureka responded with {} instead of 204: {}", other, context);
+            Err(UnexpectedStatus(u16::from(other), context))
+        }
+    }
+}
+
+#[allow(clippy::match_like_matches_macro)]
+pub(crate) fn is_not_found(error: &EurekaClientError) -> bool {
+    match *error.inner() {
+        NotFound => true,
+        _ => false
+    }
+}
+
+/// Reports a response body that could not be parsed, keeping the start of it
+pub(crate) fn deserialize_error(error: ParserError, body: &[u8], content_type: Option<&Mime>) -> EurekaClientError {
+    warn!("Could not parse a {} byte response: {}", body.len(), error);
+    Deserialize {
+        message: error.to_string(),
+        body_snippet: error_context(body),
+        content_type: content_type.map(|content_type| content_type.to_string()),
+        cause: error
+    }
+}
+
+/// Fails with `NonJsonResponse` when a body is declared to be something other than json,
+/// without a Content-Type it is parsed regardless
+pub(crate) fn check_json(content_type: Option<&Mime>, body: &[u8]) -> Result<(), EurekaClientError> {
+    let content_type = match content_type {
+        Some(content_type) => content_type,
+        None => return Ok(())
+    };
+    if content_type.subtype() == mime::JSON || content_type.suffix() == Some(mime::JSON) {
+        return Ok(());
+    }
+    let body_snippet = error_context(body);
+    warn!("Eureka responded with {} instead of json: {}", content_type, body_snippet);
+    Err(NonJsonResponse {
+        content_type: content_type.to_string(),
+        body_snippet: body_snippet
+    })
+}
+
+fn error_context(body: &[u8]) -> String {
+    let end = if body.len() > MAX_ERROR_BODY_LENGTH { MAX_ERROR_BODY_LENGTH } else { body.len() };
+    String::from_utf8_lossy(&body[..end]).into_owned()
 }
 
 impl Error for EurekaClientError {
     fn description(&self) -> &str {
         match *self {
-            ClientError(_) => "Error calling downstream client: ",
-            JsonError(_) => "A json error occurred ",
+            ClientError(_) => "Error calling eureka",
+            JsonError(_) => "A json error occurred",
+            GenericError(_) => "An error occurred",
+            InvalidUri(_) => "The uri of a request is invalid",
             BadRequest => "Received a 400 (Bad Request) response",
-            _ => "Some error occurred"
+            NotFound => "Received a 404 (Not Found) response",
+            InstanceNotFound => "The instance is not registered with eureka",
+            Unauthorized => "Received a 401 (Unauthorized) response",
+            Forbidden => "Received a 403 (Forbidden) response",
+            MethodNotAllowed => "Received a 405 (Method Not Allowed) response",
+            NotAcceptable => "Received a 406 (Not Acceptable) response",
+            Conflict => "Received a 409 (Conflict) response",
+            TooManyRequests => "Received a 429 (Too Many Requests) response",
+            InternalServerError(_) => "Received a 5xx (Server Error) response",
+            UnexpectedStatus(..) => "Received an unexpected response status",
+            TruncatedResponse(_) => "The connection closed before the response was complete",
+            Timeout { .. } => "Eureka did not respond in time",
+            DecodeError(_) => "The response body could not be decoded",
+            Connection(_) => "Could not connect to eureka",
+            Dns(_) => "Could not resolve the host name of eureka",
+            Deserialize { .. } => "The response body is not the json expected",
+            NonJsonResponse { .. } => "The response is not json",
+            RetriesExhausted { .. } => "Every attempt to reach eureka failed",
+            InvalidBaseUrl(_) => "The base url of the eureka cluster is invalid",
+            InvalidConfiguration(_) => "The client configuration is invalid",
+            OperationDisabled { .. } => "The operation is disabled for this client",
+            Request { ref error, .. } => error.description()
         }
     }
 
-    fn cause(&self) -> Option<&Error> {
+    fn source(&self) -> Option<&(Error + 'static)> {
         match *self {
-            ClientError(ref error) => Some(error as &Error),
-            JsonError(ref error) => Some(error as &Error),
+            ClientError(ref error) => Some(error),
+            JsonError(ref error) => Some(error),
+            InvalidUri(ref error) => Some(error),
+            Connection(ref error) | Dns(ref error) => Some(error),
+            Deserialize { ref cause, .. } => Some(cause),
+            RetriesExhausted { ref last, .. } => Some(&**last),
+            Request { ref error, .. } => error.source(),
             _ => None
         }
     }
 }
 
+// Io errors while connecting get their own variants, everything after stays a ClientError
 impl From<HyperError> for EurekaClientError {
     fn from(err: HyperError) -> EurekaClientError {
-        ClientError(err)
+        match err {
+            HyperError::Io(e) => match e.kind() {
+                io::ErrorKind::ConnectionRefused | io::ErrorKind::AddrNotAvailable | io::ErrorKind::NotConnected => Connection(e),
+                _ if is_lookup_failure(&e) => Dns(e),
+                _ => ClientError(HyperError::Io(e))
+            },
+            other => ClientError(other)
+        }
     }
 }
 
+// The standard library reports a failed lookup without a kind of its own, only the message
+// tells it apart
+fn is_lookup_failure(e: &io::Error) -> bool {
+    e.to_string().contains(LOOKUP_FAILURE)
+}
+
+const LOOKUP_FAILURE: &'static str = "failed to lookup address information";
+
 impl From<ParserError> for EurekaClientError {
     fn from(err: ParserError) -> EurekaClientError {
         JsonError(err)
@@ -66,6 +466,296 @@ impl From<UriError> for EurekaClientError {
 
 impl Display for EurekaClientError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.description())
+        match *self {
+            ClientError(ref e) => write!(f, "{}: {}", self.description(), e),
+            JsonError(ref e) => write!(f, "{}: {}", self.description(), e),
+            GenericError(ref message) => write!(f, "{}: {}", self.description(), message),
+            InvalidUri(ref e) => write!(f, "{}: {}", self.description(), e),
+            UnexpectedStatus(status, ref body) => write!(f, "{} {}: {}", self.description(), status, body),
+            TruncatedResponse(received) => write!(f, "{}, received {} bytes", self.description(), received),
+            Timeout { elapsed, operation } => write!(f, "{}, the {} waited {:?}", self.description(), operation, elapsed),
+            DecodeError(ref reason) => write!(f, "{}: {}", self.description(), reason),
+            Connection(ref e) | Dns(ref e) => write!(f, "{}: {}", self.description(), e),
+            Deserialize { ref message, ref body_snippet, content_type: Some(ref content_type), .. } =>
+                write!(f, "{}, {} in {}: {}", self.description(), message, content_type, body_snippet),
+            Deserialize { ref message, ref body_snippet, .. } => write!(f, "{}, {}: {}", self.description(), message, body_snippet),
+            NonJsonResponse { ref content_type, ref body_snippet } => write!(f, "{} but {}: {}", self.description(), content_type, body_snippet),
+            RetriesExhausted { attempts, ref last } => write!(f, "{} after {} attempts: {}", self.description(), attempts, last),
+            InvalidBaseUrl(ref reason) => write!(f, "{}: {}", self.description(), reason),
+            InvalidConfiguration(ref reason) => write!(f, "{}: {}", self.description(), reason),
+            OperationDisabled { operation } => write!(f, "{}: {}", self.description(), operation),
+            Request { ref context, ref error } => {
+                write!(f, "{} ({} {}", error, context.method, context.uri)?;
+                if let Some(status) = context.status {
+                    write!(f, " answered {}", status)?;
+                }
+                match **error {
+                    // these show the body themselves
+                    UnexpectedStatus(..) | Deserialize { .. } | NonJsonResponse { .. } => f.write_str(")"),
+                    _ if context.body.is_empty() => f.write_str(")"),
+                    _ => write!(f, "): {}", context.body)
+                }
+            }
+            _ => write!(f, "{}", self.description())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category() {
+        let table = vec![
+            (StatusCode::BadRequest, ErrorCategory::Client),
+            (StatusCode::NotFound, ErrorCategory::Client),
+            (StatusCode::TooManyRequests, ErrorCategory::Client),
+            (StatusCode::Gone, ErrorCategory::Client),
+            (StatusCode::InternalServerError, ErrorCategory::Server),
+            (StatusCode::ServiceUnavailable, ErrorCategory::Server),
+            (StatusCode::MovedPermanently, ErrorCategory::Server)
+        ];
+        for (status, category) in table {
+            assert_eq!(category, check_status(status, b"").unwrap_err().category(), "status {}", status);
+        }
+    }
+
+    #[test]
+    fn test_category_of_transport_errors() {
+        let io_error = ::std::io::Error::new(::std::io::ErrorKind::ConnectionRefused, "refused");
+        assert_eq!(ErrorCategory::Network, EurekaClientError::from(HyperError::Io(io_error)).category());
+        assert_eq!(ErrorCategory::Network, TruncatedResponse(12).category());
+        assert_eq!(ErrorCategory::Network, Timeout { elapsed: Duration::from_secs(5), operation: Operation::Query }.category());
+        assert_eq!(ErrorCategory::Internal, GenericError("oops".to_owned()).category());
+        let uri_error = "".parse::<::hyper::Uri>().unwrap_err();
+        assert_eq!(ErrorCategory::Client, EurekaClientError::from(uri_error).category());
+    }
+
+    #[test]
+    fn test_connect_failures_are_told_apart() {
+        let io_error = |kind, message| EurekaClientError::from(HyperError::Io(io::Error::new(kind, message)));
+        match io_error(io::ErrorKind::ConnectionRefused, "refused") {
+            Connection(ref e) => assert_eq!(io::ErrorKind::ConnectionRefused, e.kind()),
+            other => panic!("expected Connection, got {:?}", other)
+        }
+        match io_error(io::ErrorKind::Other, "failed to lookup address information: Name or service not known") {
+            Dns(ref e) => assert!(e.to_string().contains("Name or service not known")),
+            other => panic!("expected Dns, got {:?}", other)
+        }
+        match io_error(io::ErrorKind::ConnectionReset, "reset") {
+            ClientError(HyperError::Io(ref e)) => assert_eq!(io::ErrorKind::ConnectionReset, e.kind()),
+            other => panic!("expected ClientError, got {:?}", other)
+        }
+        assert!(io_error(io::ErrorKind::ConnectionRefused, "refused").is_connect_error());
+        assert!(!io_error(io::ErrorKind::ConnectionReset, "reset").is_connect_error());
+    }
+
+    #[test]
+    fn test_is_retryable_and_status() {
+        let io_error = |kind| io::Error::new(kind, "failed");
+        let answered = |error: EurekaClientError, code| error.in_request(RequestContext::new(
+            &Method::Get, "http://eureka:8761/v2/apps", Some(StatusCode::try_from(code).unwrap()), b""));
+        let table: Vec<(EurekaClientError, bool, Option<u16>)> = vec![
+            (ClientError(HyperError::Incomplete), true, None),
+            (JsonError(ParserError::custom("bad key")), false, None),
+            (GenericError("oops".to_owned()), false, None),
+            (EurekaClientError::from("".parse::<::hyper::Uri>().unwrap_err()), false, None),
+            (InvalidBaseUrl("no scheme".to_owned()), false, None),
+            (InvalidConfiguration("no client name".to_owned()), false, None),
+            (InternalServerError(500), true, Some(500)),
+            (InternalServerError(501), false, Some(501)),
+            (BadRequest, false, Some(400)),
+            (NotFound, false, Some(404)),
+            (InstanceNotFound, false, None),
+            (Unauthorized, false, Some(401)),
+            (Forbidden, false, Some(403)),
+            (MethodNotAllowed, false, Some(405)),
+            (NotAcceptable, false, Some(406)),
+            (Conflict, false, Some(409)),
+            (TooManyRequests, true, Some(429)),
+            (UnexpectedStatus(418, "teapot".to_owned()), false, Some(418)),
+            (TruncatedResponse(10), true, None),
+            (Timeout { elapsed: Duration::from_secs(1), operation: Operation::Query }, true, None),
+            (DecodeError("the gzip stream ends early".to_owned()), false, None),
+            (Connection(io_error(io::ErrorKind::ConnectionRefused)), true, None),
+            (Dns(io_error(io::ErrorKind::Other)), true, None),
+            (deserialize_error(ParserError::custom("expected value"), b"<html>", None), false, None),
+            (check_json(Some(&mime::TEXT_HTML), b"<html>").unwrap_err(), false, None),
+            (RetriesExhausted { attempts: 2, last: Box::new(Timeout { elapsed: Duration::from_secs(1), operation: Operation::Query }) }, true, None),
+            (RetriesExhausted { attempts: 2, last: Box::new(answered(NotFound, 404)) }, false, Some(404)),
+            (OperationDisabled { operation: Operation::Register }, false, None),
+            (answered(InternalServerError(500), 500), true, Some(500)),
+            (answered(InternalServerError(501), 501), false, Some(501)),
+            (answered(InternalServerError(502), 502), true, Some(502)),
+            (answered(InternalServerError(503), 503), true, Some(503)),
+            (answered(InternalServerError(504), 504), true, Some(504)),
+            (answered(InternalServerError(505), 505), false, Some(505)),
+            (answered(TooManyRequests, 429), true, Some(429)),
+            (answered(BadRequest, 400), false, Some(400)),
+            (answered(Unauthorized, 401), false, Some(401)),
+            (answered(InstanceNotFound, 404), false, Some(404)),
+            (answered(deserialize_error(ParserError::custom("expected value"), b"<html>", None), 200), false, Some(200)),
+            (Timeout { elapsed: Duration::from_secs(1), operation: Operation::Query }.in_request(
+                RequestContext::new(&Method::Get, "http://eureka:8761/v2/apps", None, b"")), true, None)
+        ];
+        for (error, retryable, status) in table {
+            assert_eq!(retryable, error.is_retryable(), "{:?}", error);
+            assert_eq!(status, error.status().map(u16::from), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_sources_are_kept() {
+        let refused = EurekaClientError::from(HyperError::Io(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")));
+        let exhausted = RetriesExhausted { attempts: 2, last: Box::new(refused) };
+
+        let last = exhausted.source().unwrap();
+        assert!(last.to_string().starts_with("Could not connect to eureka"));
+        let io_error = last.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(io::ErrorKind::ConnectionRefused, io_error.kind());
+
+        let parsed = ::serde_json::from_slice::<::serde_json::Value>(b"{").unwrap_err();
+        let garbled = deserialize_error(parsed, b"{", None);
+        assert!(garbled.source().unwrap().downcast_ref::<ParserError>().is_some());
+        assert!(NotFound.source().is_none());
+    }
+
+    #[test]
+    fn test_display_names_the_cause() {
+        let displayed = vec![
+            (EurekaClientError::from(HyperError::Incomplete), "Error calling eureka: "),
+            (JsonError(ParserError::custom("bad key")), "A json error occurred: bad key"),
+            (GenericError("oops".to_owned()), "An error occurred: oops"),
+            (EurekaClientError::from("".parse::<::hyper::Uri>().unwrap_err()), "The uri of a request is invalid: "),
+            (UnexpectedStatus(418, "teapot".to_owned()), "Received an unexpected response status 418: teapot"),
+            (NotFound, "Received a 404 (Not Found) response")
+        ];
+        for (error, start) in displayed {
+            let display = error.to_string();
+            assert!(display.starts_with(start), "{:?} displays as {:?}", error, display);
+            assert!(display.len() > start.len() || !start.ends_with(": "), "{:?} displays without its cause", error);
+        }
+    }
+
+    #[test]
+    fn test_request_context() {
+        let context = |status, body: &[u8]| RequestContext::new(&Method::Get, "http://eureka:8761/v2/apps", status, body);
+        let error = InternalServerError(502).in_request(context(Some(StatusCode::BadGateway), b"upstream down"));
+        assert_eq!("Received a 5xx (Server Error) response (GET http://eureka:8761/v2/apps answered 502): upstream down", error.to_string());
+        assert_eq!(Some(502), error.request().unwrap().status);
+        assert_eq!(ErrorCategory::Server, error.category());
+        assert!(error.is_retryable());
+        match *error.inner() {
+            InternalServerError(502) => (),
+            ref other => panic!("expected InternalServerError, got {:?}", other)
+        }
+
+        // the first request attached is kept, the start of the body once
+        let error = UnexpectedStatus(418, "teapot".to_owned()).in_request(context(Some(StatusCode::Unregistered(418)), b"teapot"));
+        let error = error.in_request(context(None, b""));
+        assert_eq!("Received an unexpected response status 418: teapot (GET http://eureka:8761/v2/apps answered 418)", error.to_string());
+
+        let refused = EurekaClientError::from(HyperError::Io(io::Error::new(io::ErrorKind::ConnectionRefused, "refused")));
+        let exhausted = RetriesExhausted { attempts: 2, last: Box::new(refused.in_request(context(None, b""))) };
+        assert_eq!(None, exhausted.request().unwrap().status);
+        assert!(NotFound.request().is_none());
+        assert_eq!(vec![b'a'; MAX_ERROR_BODY_LENGTH], context(None, &vec![b'a'; MAX_ERROR_BODY_LENGTH * 2]).body.into_bytes());
+    }
+
+    #[test]
+    fn test_deserialize_error_keeps_the_body() {
+        let body = b"<html>Bad gateway</html>";
+        let parsed = ::serde_json::from_slice::<::serde_json::Value>(body).unwrap_err();
+        let error = deserialize_error(parsed, body, Some(&mime::APPLICATION_JSON));
+        match error {
+            Deserialize { ref body_snippet, ref content_type, .. } => {
+                assert_eq!("<html>Bad gateway</html>", body_snippet);
+                assert_eq!(Some("application/json"), content_type.as_ref().map(|s| s.as_str()));
+            }
+            ref other => panic!("expected Deserialize, got {:?}", other)
+        }
+        assert!(error.to_string().contains(" in application/json: <html>"), "{}", error);
+    }
+
+    #[test]
+    fn test_check_json() {
+        let mime = |raw: &str| raw.parse::<Mime>().unwrap();
+        assert!(check_json(None, b"{}").is_ok());
+        assert!(check_json(Some(&mime("application/json")), b"{}").is_ok());
+        assert!(check_json(Some(&mime("application/json; charset=utf-8")), b"{}").is_ok());
+        assert!(check_json(Some(&mime("application/vnd.eureka.v2+json")), b"{}").is_ok());
+        match check_json(Some(&mime("text/html")), b"<html>502 Bad Gateway</html>") {
+            Err(ref error @ NonJsonResponse { .. }) => {
+                assert_eq!("The response is not json but text/html: <html>502 Bad Gateway</html>", error.to_string());
+                assert_eq!(ErrorCategory::Server, error.category());
+                assert!(!error.is_retryable());
+            }
+            other => panic!("expected NonJsonResponse, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_duplicate_keeps_category() {
+        let io_error = ::std::io::Error::new(::std::io::ErrorKind::ConnectionRefused, "refused");
+        let errors = vec![
+            EurekaClientError::from(HyperError::Io(io_error)),
+            EurekaClientError::from(HyperError::Incomplete),
+            NotFound,
+            InstanceNotFound,
+            UnexpectedStatus(418, "teapot".to_owned()),
+            InvalidBaseUrl("no".to_owned()),
+            OperationDisabled { operation: Operation::Deregister },
+            TruncatedResponse(3),
+            Timeout { elapsed: Duration::from_millis(250), operation: Operation::Query },
+            DecodeError("the gzip stream ends early".to_owned()),
+            Dns(io::Error::new(io::ErrorKind::Other, "failed to lookup address information")),
+            deserialize_error(ParserError::custom("expected value"), b"<html>", None),
+            check_json(Some(&mime::TEXT_HTML), b"<html>").unwrap_err(),
+            RetriesExhausted { attempts: 3, last: Box::new(TruncatedResponse(5)) },
+            NotFound.in_request(RequestContext::new(&Method::Delete, "http://eureka:8761/v2/apps/A/a", Some(StatusCode::NotFound), b""))
+        ];
+        for error in errors {
+            let copy = error.duplicate();
+            assert_eq!(error.category(), copy.category());
+            assert_eq!(error.to_string(), copy.to_string());
+        }
+    }
+
+    #[test]
+    fn test_no_content_contract() {
+        assert!(check_no_content(StatusCode::NoContent, b"").is_ok());
+        assert!(check_no_content(StatusCode::Ok, b"").is_ok());
+        match check_no_content(StatusCode::Accepted, b"queued") {
+            Err(UnexpectedStatus(202, ref body)) => assert_eq!("queued", body),
+            other => panic!("unexpected {:?}", other)
+        }
+        match check_no_content(StatusCode::ServiceUnavailable, b"") {
+            Err(InternalServerError(503)) => (),
+            other => panic!("unexpected {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_success_is_ok() {
+        assert!(check_status(StatusCode::Ok, b"").is_ok());
+        assert!(check_status(StatusCode::NoContent, b"").is_ok());
+    }
+
+    #[test]
+    fn test_unexpected_status_keeps_bounded_body() {
+        let body = vec![b'a'; MAX_ERROR_BODY_LENGTH * 2];
+        match check_status(StatusCode::Unregistered(418), &body) {
+            Err(UnexpectedStatus(418, ref context)) => assert_eq!(MAX_ERROR_BODY_LENGTH, context.len()),
+            other => panic!("expected UnexpectedStatus, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_non_json_body_is_lossy() {
+        match check_status(StatusCode::MovedPermanently, &[0xff, b'<', b'h', b'>']) {
+            Err(UnexpectedStatus(301, ref context)) => assert_eq!("\u{fffd}<h>", context),
+            other => panic!("expected UnexpectedStatus, got {:?}", other)
+        }
     }
 }
\ No newline at end of file
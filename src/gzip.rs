@@ -0,0 +1,86 @@
+// Gzip decoding for response bodies, through flate2
+//
+// Eureka only ever sends small to medium registries, so the whole body is decoded in one go,
+// up to a maximum size so a small body can not expand without bound. Errors are plain
+// messages, they end up in EurekaClientError::DecodeError.
+use std::io::Read;
+use flate2::Crc;
+use flate2::read::GzDecoder;
+
+/// Decodes a whole gzip member, failing once more than `max_size` bytes come out of it
+pub(crate) fn decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    // one byte past the maximum tells a body of exactly `max_size` from a larger one
+    GzDecoder::new(data)
+        .take(max_size as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| format!("the gzip stream could not be decoded: {}", e))?;
+    if decoded.len() > max_size {
+        return Err(format!("the gzip body decodes to more than {} bytes", max_size));
+    }
+    Ok(decoded)
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc::new();
+    crc.update(data);
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_SIZE: usize = 1024 * 1024;
+    const BODY: &'static [u8] = br#"{"applications":{"versions__delta":1,"apps__hashcode":"UP_1_"}}"#;
+    // gzip.compress(BODY, compresslevel=9, mtime=0), a single fixed huffman block
+    const FIXED: &'static [u8] = &[
+        0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xff, 0xab, 0x56, 0x4a, 0x2c, 0x28, 0xc8, 0xc9, 0x4c, 0x4e,
+        0x2c, 0xc9, 0xcc, 0xcf, 0x2b, 0x56, 0xb2, 0xaa, 0x56, 0x2a, 0x4b, 0x2d, 0x2a, 0x06, 0xb1, 0xe3, 0xe3, 0x53, 0x52,
+        0x73, 0x4a, 0x12, 0x95, 0xac, 0x0c, 0x75, 0x40, 0x6a, 0x80, 0xfc, 0x8c, 0xc4, 0xe2, 0x8c, 0xe4, 0xfc, 0x94, 0x54,
+        0x25, 0x2b, 0xa5, 0xd0, 0x80, 0x78, 0xc3, 0x78, 0xa5, 0xda, 0x5a, 0x00, 0x9e, 0x2d, 0x56, 0x27, 0x3f, 0x00, 0x00,
+        0x00];
+
+    #[test]
+    fn test_fixed_block() {
+        assert_eq!(BODY, &decompress(FIXED, MAX_SIZE).unwrap()[..]);
+    }
+
+    #[test]
+    fn test_dynamic_blocks_and_file_name() {
+        let expected = include_bytes!("../tests/fixtures/registry.json");
+        let decoded = decompress(include_bytes!("../tests/fixtures/registry.json.gz"), MAX_SIZE).unwrap();
+        assert_eq!(expected.len(), decoded.len());
+        assert!(expected[..] == decoded[..]);
+    }
+
+    #[test]
+    fn test_maximum_size() {
+        assert_eq!(BODY, &decompress(FIXED, BODY.len()).unwrap()[..]);
+        let error = decompress(FIXED, BODY.len() - 1).unwrap_err();
+        assert!(error.contains("more than"), "{}", error);
+        assert!(decompress(include_bytes!("../tests/fixtures/gzip_bomb.gz"), MAX_SIZE).is_err());
+    }
+
+    #[test]
+    fn test_truncated_streams_are_rejected() {
+        for &end in &[5, 20, FIXED.len() - 8, FIXED.len() - 1] {
+            assert!(decompress(&FIXED[..end], MAX_SIZE).is_err(), "cut at {}", end);
+        }
+    }
+
+    #[test]
+    fn test_corrupt_streams_are_rejected() {
+        assert!(decompress(BODY, MAX_SIZE).is_err());
+        let mut corrupt = FIXED.to_vec();
+        let crc_at = corrupt.len() - 8;
+        corrupt[crc_at] ^= 0xff;
+        assert!(decompress(&corrupt, MAX_SIZE).is_err());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0, crc32(b""));
+    }
+}
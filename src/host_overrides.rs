@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::io;
+use std::net::IpAddr;
+use std::rc::Rc;
+use futures::{future, Future};
+use hyper::Uri;
+use hyper::client::{HttpConnector, Service};
+use tokio_core::reactor::Handle;
+use watched_stream::{Connections, WatchedStream};
+
+// hyper's default number of dns threads
+const DNS_THREADS: usize = 4;
+
+/// Connects to the eureka servers, using a fixed address instead of dns for overridden hosts
+///
+/// Only the connection is redirected, the request keeps its uri so the Host header still
+/// carries the original host name. Every connection is watched, see `watched_stream`.
+#[derive(Clone)]
+pub(crate) struct HostOverrideConnector {
+    http: HttpConnector,
+    overrides: Rc<HashMap<String, IpAddr>>,
+    connections: Rc<Connections>
+}
+
+impl HostOverrideConnector {
+    pub(crate) fn new(handle: &Handle, overrides: Rc<HashMap<String, IpAddr>>, connections: Rc<Connections>) -> HostOverrideConnector {
+        HostOverrideConnector {
+            http: HttpConnector::new(DNS_THREADS, handle),
+            overrides: overrides,
+            connections: connections
+        }
+    }
+}
+
+impl Service for HostOverrideConnector {
+    type Request = Uri;
+    type Response = WatchedStream;
+    type Error = io::Error;
+    type Future = Box<Future<Item=WatchedStream, Error=io::Error>>;
+
+    fn call(&self, uri: Uri) -> Self::Future {
+        let connecting = match override_uri(&uri, &self.overrides) {
+            Some(Ok(overridden)) => {
+                debug!("Connecting to {} instead of resolving {:?}", overridden, uri.host());
+                self.http.call(overridden)
+            }
+            Some(Err(e)) => return Box::new(future::err(io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))),
+            None => self.http.call(uri)
+        };
+        let connections = self.connections.clone();
+        Box::new(connecting.map(move |stream| Connections::watch(&connections, stream)))
+    }
+}
+
+fn override_uri(uri: &Uri, overrides: &HashMap<String, IpAddr>) -> Option<Result<Uri, ::hyper::error::UriError>> {
+    let ip = uri.host().and_then(|host| overrides.get(&host.to_lowercase()))?;
+    let scheme = uri.scheme().unwrap_or("http");
+    let port = uri.port().unwrap_or_else(|| if scheme == "https" { 443 } else { 80 });
+    let host = match *ip {
+        IpAddr::V4(ref ip) => ip.to_string(),
+        IpAddr::V6(ref ip) => format!("[{}]", ip)
+    };
+    Some(format!("{}://{}:{}{}", scheme, host, port, uri.path()).parse())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides() -> HashMap<String, IpAddr> {
+        let mut overrides = HashMap::new();
+        overrides.insert("eureka.internal".to_owned(), "10.0.0.5".parse().unwrap());
+        overrides.insert("eureka6.internal".to_owned(), "::1".parse().unwrap());
+        overrides
+    }
+
+    fn rewrite(uri: &str) -> Option<String> {
+        override_uri(&uri.parse().unwrap(), &overrides()).map(|uri| uri.unwrap().to_string())
+    }
+
+    #[test]
+    fn test_override_uri() {
+        assert_eq!(Some("http://10.0.0.5:8761/v2/apps".to_owned()), rewrite("http://eureka.internal:8761/v2/apps"));
+        assert_eq!(Some("http://10.0.0.5:80/".to_owned()), rewrite("http://EUREKA.internal/"));
+        assert_eq!(Some("http://[::1]:8761/".to_owned()), rewrite("http://eureka6.internal:8761/"));
+        assert_eq!(None, rewrite("http://elsewhere:8761/v2/apps"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CRATE_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+lazy_static! {
+    // A random uuid that stays the same for the lifetime of the process
+    static ref PROCESS_ID: String = random_uuid();
+}
+
+/// How the [EurekaClient](struct.EurekaClient.html) identifies itself to Eureka
+///
+/// These are sent as the `DiscoveryIdentity-*` headers on every request, Eureka servers use them
+/// for logging and for their rate limiter whitelists. Every field can be overridden, the
+/// whitelists match names exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveryIdentity {
+    /// Defaults to the client name
+    pub name: String,
+    /// Defaults to the version of this crate
+    pub version: String,
+    /// Defaults to a uuid generated once per process
+    pub id: String
+}
+
+impl DiscoveryIdentity {
+    /// Creates the default identity for a client
+    pub fn new(client_name: &str) -> DiscoveryIdentity {
+        DiscoveryIdentity {
+            name: client_name.to_owned(),
+            version: CRATE_VERSION.to_owned(),
+            id: PROCESS_ID.clone()
+        }
+    }
+}
+
+// Formats 128 random bits as a version 4 uuid. The id only has to tell processes apart in
+// eureka's logs, it is sent in the clear and is no secret, so it needs to be unique rather than
+// unguessable. The std hasher keys are seeded randomly for every process, and mixing in the
+// process id and the time is plenty for that.
+fn random_uuid() -> String {
+    let high = random_u64(0);
+    let low = random_u64(1);
+    format!("{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+            high >> 32,
+            (high >> 16) & 0xffff,
+            high & 0x0fff,
+            ((low >> 48) & 0x3fff) | 0x8000,
+            low & 0xffff_ffff_ffff)
+}
+
+fn random_u64(salt: u64) -> u64 {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(salt);
+    hasher.write_u32(process::id());
+    if let Ok(now) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        hasher.write_u64(now.as_secs());
+        hasher.write_u32(now.subsec_nanos());
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let identity = DiscoveryIdentity::new("MY-APP");
+        assert_eq!("MY-APP", identity.name);
+        assert_eq!(CRATE_VERSION, identity.version);
+        assert_eq!(*PROCESS_ID, identity.id);
+    }
+
+    #[test]
+    fn test_process_id_is_stable() {
+        assert_eq!(DiscoveryIdentity::new("ONE").id, DiscoveryIdentity::new("OTHER").id);
+        assert_eq!(*PROCESS_ID, DiscoveryIdentity::new("OTHER").id);
+    }
+
+    #[test]
+    fn test_process_id_is_a_v4_uuid() {
+        let id: &str = &PROCESS_ID;
+        let groups: Vec<usize> = id.split('-').map(|g| g.len()).collect();
+        assert_eq!(vec![8, 4, 4, 4, 12], groups);
+        assert!(id.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+        assert_eq!(Some('4'), id.chars().nth(14));
+        assert!("89ab".contains(id.chars().nth(19).unwrap()));
+    }
+
+    #[test]
+    fn test_random_uuids_differ() {
+        assert!(random_uuid() != random_uuid());
+    }
+}
@@ -0,0 +1,135 @@
+//! The registration lifecycle of a service, independent of its web framework
+//!
+//! [LifecycleHooks](struct.LifecycleHooks.html) registers the instance as STARTING on startup,
+//! marks it UP once the service is ready, and on shutdown takes it out of service, waits for
+//! clients to stop sending traffic and deregisters it. Frameworks only call the hooks and serve
+//! [readiness](struct.LifecycleHooks.html#method.readiness) from their readiness endpoint.
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+use futures::{future, Future};
+use hyper::Error as HyperError;
+use tokio_core::reactor::{Handle, Timeout};
+use errors::EurekaClientError;
+use eureka_client::EurekaClient;
+use request::{Instance, RegisterRequest, Status};
+
+/// Where the instance stands in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReadinessStatus {
+    /// Not registered yet, or the registration failed
+    NotRegistered,
+    /// Registered as STARTING, the service is not ready yet
+    Starting,
+    /// Registered as UP
+    Ready,
+    /// Out of service, waiting for clients to drain before deregistering
+    Draining,
+    /// Deregistered
+    Stopped
+}
+
+impl ReadinessStatus {
+    /// Whether the service should receive traffic
+    pub fn is_ready(&self) -> bool {
+        *self == ReadinessStatus::Ready
+    }
+}
+
+/// Runs the registration lifecycle of one instance, see the [module](index.html) docs
+///
+/// Each request is bounded by the client's [timeout](../struct.EurekaClient.html#method.set_timeout),
+/// set one so a hanging server can not hold up startup or shutdown.
+#[derive(Clone)]
+pub struct LifecycleHooks<'a> {
+    client: Rc<EurekaClient<'a>>,
+    handle: Handle,
+    instance: Rc<Instance>,
+    drain: Duration,
+    status: Rc<Cell<ReadinessStatus>>
+}
+
+impl<'a> LifecycleHooks<'a> {
+    /// # Arguments
+    ///
+    /// * `client` - The client the instance registers through
+    /// * `handle` - The handle the drain period is timed on
+    /// * `instance` - The instance to register, its status is set by the hooks
+    /// * `drain` - How long the instance stays out of service before it deregisters
+    pub fn new(client: EurekaClient<'a>, handle: &Handle, instance: Instance, drain: Duration) -> LifecycleHooks<'a> {
+        LifecycleHooks {
+            client: Rc::new(client),
+            handle: handle.clone(),
+            instance: Rc::new(instance),
+            drain: drain,
+            status: Rc::new(Cell::new(ReadinessStatus::NotRegistered))
+        }
+    }
+
+    pub fn readiness(&self) -> ReadinessStatus {
+        self.status.get()
+    }
+
+    /// Registers the instance as STARTING, and as UP once `ready` resolves
+    ///
+    /// When `ready` fails the instance stays registered as STARTING and the error is returned.
+    pub fn on_startup<R>(&self, ready: R) -> Box<Future<Item=(), Error=EurekaClientError> + 'a>
+        where R: Future<Item=(), Error=EurekaClientError> + 'a {
+        let hooks = self.clone();
+        let status = self.status.clone();
+        let result = self.register(Status::Starting)
+            .map(move |_| status.set(ReadinessStatus::Starting))
+            .and_then(|_| ready)
+            .and_then(move |_| {
+                let status = hooks.status.clone();
+                hooks.register(Status::Up).map(move |_| status.set(ReadinessStatus::Ready))
+            });
+        Box::new(result)
+    }
+
+    /// Takes the instance out of service, waits for the drain period and deregisters it
+    ///
+    /// Failing to take the instance out of service is logged and the instance deregisters
+    /// anyway. Nothing is sent when the instance never registered or already stopped.
+    pub fn on_shutdown(&self) -> Box<Future<Item=(), Error=EurekaClientError> + 'a> {
+        match self.status.get() {
+            ReadinessStatus::NotRegistered | ReadinessStatus::Stopped => return Box::new(future::ok(())),
+            _ => self.status.set(ReadinessStatus::Draining)
+        }
+        let drain = self.drain;
+        let timer = match Timeout::new(drain, &self.handle) {
+            Ok(timer) => timer,
+            Err(e) => return Box::new(future::err(EurekaClientError::from(HyperError::Io(e))))
+        };
+        let instance = self.instance.clone();
+        let hooks = self.clone();
+        let result = self.register(Status::OutOfService)
+            .then(move |result| -> Box<Future<Item=(), Error=EurekaClientError>> {
+                if let Err(e) = result {
+                    warn!("Could not take {} out of service, deregistering without draining: {}", instance.id(), e);
+                    return Box::new(future::ok(()));
+                }
+                debug!("Draining {} for {:?}", instance.id(), drain);
+                Box::new(timer.map_err(|e| EurekaClientError::from(HyperError::Io(e))))
+            })
+            .and_then(move |_| {
+                let status = hooks.status.clone();
+                hooks.client.deregister(&hooks.instance.app, hooks.instance.id())
+                    .map(move |_| status.set(ReadinessStatus::Stopped))
+            });
+        Box::new(result)
+    }
+
+    /// Runs [on_shutdown](#method.on_shutdown) once `signal` resolves, whether it succeeds or fails
+    pub fn shutdown_on<S>(&self, signal: S) -> Box<Future<Item=(), Error=EurekaClientError> + 'a>
+        where S: Future + 'a {
+        let hooks = self.clone();
+        Box::new(signal.then(move |_| hooks.on_shutdown()))
+    }
+
+    fn register(&self, status: Status) -> Box<Future<Item=(), Error=EurekaClientError>> {
+        let mut instance = (*self.instance).clone();
+        instance.status = status;
+        self.client.register(&self.instance.app, &RegisterRequest::new(instance))
+    }
+}
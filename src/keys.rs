@@ -0,0 +1,63 @@
+//! Well known identifiers used when talking to Eureka
+//!
+//! The metadata keys, header names and status values are defined by Eureka itself
+//! and are considered stable API. The default ports, lease values and path templates
+//! mirror what a stock Eureka server uses and may change along with those servers.
+
+// Metadata keys
+pub const METADATA_MANAGEMENT_PORT: &'static str = "management.port";
+pub const METADATA_ZONE: &'static str = "zone";
+pub const METADATA_VERSION: &'static str = "version";
+
+// Header names
+pub const HEADER_DISCOVERY_REPLICATION: &'static str = "x-netflix-discovery-replication";
+pub const HEADER_DISCOVERY_IDENTITY_NAME: &'static str = "DiscoveryIdentity-Name";
+pub const HEADER_DISCOVERY_IDENTITY_VERSION: &'static str = "DiscoveryIdentity-Version";
+pub const HEADER_DISCOVERY_IDENTITY_ID: &'static str = "DiscoveryIdentity-Id";
+
+// Status values
+pub const STATUS_UP: &'static str = "UP";
+pub const STATUS_DOWN: &'static str = "DOWN";
+pub const STATUS_STARTING: &'static str = "STARTING";
+pub const STATUS_OUT_OF_SERVICE: &'static str = "OUT_OF_SERVICE";
+pub const STATUS_UNKNOWN: &'static str = "UNKNOWN";
+
+// Default ports
+pub const DEFAULT_EUREKA_PORT: u16 = 8761;
+pub const DEFAULT_PORT: u16 = 80;
+pub const DEFAULT_SECURE_PORT: u16 = 443;
+
+// Lease defaults
+pub const DEFAULT_RENEWAL_INTERVAL_IN_SECS: u32 = 30;
+pub const DEFAULT_EVICTION_DURATION_IN_SECS: u32 = 90;
+
+// How long a missing application is remembered, see EurekaClient::enable_negative_cache
+pub const DEFAULT_NEGATIVE_CACHE_TTL_SECS: u64 = 5;
+
+// The most a gzip encoded response may decode to, see EurekaClient::set_max_decoded_size
+pub const DEFAULT_MAX_DECODED_SIZE: usize = 64 * 1024 * 1024;
+
+// How old a persisted registry snapshot may be to bootstrap from, see SnapshotPolicy
+pub const DEFAULT_SNAPSHOT_MAX_AGE_SECS: u64 = 3600;
+
+// The api prefix the endpoints are served under, Spring Cloud servers use /eureka
+pub const DEFAULT_API_PREFIX: &'static str = "/v2";
+
+// Endpoint path templates relative to the api prefix, see [Endpoint](../enum.Endpoint.html)
+pub const APPS_PATH: &'static str = "/apps";
+pub const APPS_DELTA_PATH: &'static str = "/apps/delta";
+pub const APPS_IN_REGIONS_PATH: &'static str = "/apps/";
+pub const APP_PATH: &'static str = "/apps/{appId}";
+pub const INSTANCE_PATH: &'static str = "/apps/{appId}/{instanceId}";
+pub const STATUS_OVERRIDE_PATH: &'static str = "/apps/{appId}/{instanceId}/status";
+pub const METADATA_PATH: &'static str = "/apps/{appId}/{instanceId}/metadata";
+pub const INSTANCE_BY_ID_PATH: &'static str = "/instances/{instanceId}";
+pub const VIP_PATH: &'static str = "/vips/{vipAddress}";
+pub const SVIP_PATH: &'static str = "/svips/{svipAddress}";
+pub const HEALTH_CHECK_PATH: &'static str = "/healthcheck";
+
+// Query parameters sent with heartbeats, status overrides and registry queries
+pub const PARAM_STATUS: &'static str = "status";
+pub const PARAM_LAST_DIRTY_TIMESTAMP: &'static str = "lastDirtyTimestamp";
+pub const PARAM_VALUE: &'static str = "value";
+pub const PARAM_REGIONS: &'static str = "regions";
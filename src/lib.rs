@@ -6,14 +6,59 @@ extern crate serde_json;
 extern crate futures;
 extern crate hyper;
 extern crate tokio_core;
+extern crate tokio_io;
+extern crate flate2;
+#[macro_use]
+extern crate lazy_static;
 extern crate url;
 #[macro_use]
 extern crate log;
 extern crate option_filter;
 
+pub mod audit;
+pub mod credentials;
+pub mod diagnostics;
 pub mod errors;
 pub mod eureka_client;
+#[cfg(feature = "integrations")]
+pub mod integrations;
+pub mod keys;
+pub mod metrics;
+pub mod probe;
 pub mod request;
 pub mod response;
+mod base_url;
+mod call_options;
+mod client_builder;
+mod compatibility;
+mod duration;
+mod effective_config;
+mod endpoint;
+mod gzip;
+mod host_overrides;
+mod identity;
+mod metadata;
+mod negative_cache;
+mod operation;
+mod read_only;
+mod retry;
+mod vip;
+mod watchdog;
+mod watched_stream;
+#[cfg(feature = "blocking")]
+mod blocking;
 
 pub use eureka_client::EurekaClient;
+pub use client_builder::EurekaClientBuilder;
+pub use read_only::ReadOnlyEurekaClient;
+pub use watchdog::{Watchdog, WatchdogReport};
+pub use compatibility::{CheckStep, CompatibilityReport, PortFormat, ServerQuirks, StepOutcome};
+pub use effective_config::EffectiveConfig;
+pub use endpoint::Endpoint;
+pub use identity::DiscoveryIdentity;
+pub use metadata::MetadataExt;
+pub use operation::Operation;
+pub use retry::{RetryBudget, RetryPolicy};
+pub use call_options::CallOptions;
+#[cfg(feature = "blocking")]
+pub use blocking::{discover, discover_vip};
@@ -0,0 +1,81 @@
+use serde_json::{Map, Value};
+
+/// Case-insensitive lookups on instance metadata
+///
+/// Eureka compares metadata keys case-sensitively, yet the same key is spelled `zone` by some
+/// services and `Zone` by others. These lookups match either spelling while the map keeps the
+/// keys as they were written, so they serialize unchanged. When several keys differ only by
+/// case the first one in key order wins.
+pub trait MetadataExt {
+    /// The value of the first key equal to `key` ignoring case, an exact match is preferred
+    fn get_ci(&self, key: &str) -> Option<&Value>;
+
+    /// Whether any key equals `key` ignoring case
+    fn contains_ci(&self, key: &str) -> bool {
+        self.get_ci(key).is_some()
+    }
+
+    /// The groups of keys that only differ by case, each group in key order
+    fn case_conflicts(&self) -> Vec<Vec<&str>>;
+}
+
+impl MetadataExt for Map<String, Value> {
+    fn get_ci(&self, key: &str) -> Option<&Value> {
+        self.get(key).or_else(|| {
+            let key = key.to_lowercase();
+            self.iter().find(|&(k, _)| k.to_lowercase() == key).map(|(_, v)| v)
+        })
+    }
+
+    fn case_conflicts(&self) -> Vec<Vec<&str>> {
+        let mut groups: Vec<(String, Vec<&str>)> = Vec::new();
+        for key in self.keys() {
+            let lowercase = key.to_lowercase();
+            match groups.iter().position(|&(ref k, _)| *k == lowercase) {
+                Some(at) => groups[at].1.push(key),
+                None => groups.push((lowercase, vec![key]))
+            }
+        }
+        groups.into_iter().map(|(_, keys)| keys).filter(|keys| keys.len() > 1).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(pairs: &[(&str, &str)]) -> Map<String, Value> {
+        pairs.iter().map(|&(k, v)| (k.to_owned(), Value::String(v.to_owned()))).collect()
+    }
+
+    #[test]
+    fn test_get_ci() {
+        let metadata = metadata(&[("Zone", "primary"), ("management.port", "8081")]);
+        assert_eq!(Some(&Value::String("primary".to_owned())), metadata.get_ci("zone"));
+        assert_eq!(Some(&Value::String("primary".to_owned())), metadata.get_ci("ZONE"));
+        assert_eq!(Some(&Value::String("8081".to_owned())), metadata.get_ci("Management.Port"));
+        assert!(metadata.contains_ci("zONe"));
+        assert!(!metadata.contains_ci("version"));
+        assert_eq!(None, metadata.get("zone"));
+    }
+
+    #[test]
+    fn test_exact_match_wins() {
+        let metadata = metadata(&[("ZONE", "a"), ("Zone", "b"), ("zone", "c")]);
+        assert_eq!(Some(&Value::String("b".to_owned())), metadata.get_ci("Zone"));
+        assert_eq!(Some(&Value::String("a".to_owned())), metadata.get_ci("zOne"));
+    }
+
+    #[test]
+    fn test_case_conflicts() {
+        let metadata = metadata(&[("zone", "a"), ("Zone", "b"), ("version", "1"), ("VERSION", "2"), ("region", "x")]);
+        assert_eq!(vec![vec!["VERSION", "version"], vec!["Zone", "zone"]], metadata.case_conflicts());
+        assert!(self::metadata(&[("zone", "a")]).case_conflicts().is_empty());
+    }
+
+    #[test]
+    fn test_casing_is_kept_when_serialized() {
+        let metadata = metadata(&[("Zone", "primary")]);
+        assert_eq!(r#"{"Zone":"primary"}"#, ::serde_json::to_string(&metadata).unwrap());
+    }
+}
@@ -0,0 +1,27 @@
+//! Counts of the failures a client runs into, by whose fault they are
+//!
+//! Every attempt of a request to eureka that fails, or that eureka answers with an error
+//! status, is reported to the client's [ErrorMetrics](trait.ErrorMetrics.html) with its
+//! [ErrorCategory](../errors/enum.ErrorCategory.html), also when it is retried. A body that
+//! can not be parsed is not reported. The default discards them.
+use errors::ErrorCategory;
+use operation::Operation;
+
+/// Receives the category of every failed attempt, to split error budgets by
+pub trait ErrorMetrics {
+    fn failure(&self, operation: Operation, category: ErrorCategory);
+
+    /// A failure that was not retried because the retry budget of its category is spent, see
+    /// [RetryBudget](../struct.RetryBudget.html)
+    fn retry_budget_exhausted(&self, operation: Operation, category: ErrorCategory);
+}
+
+/// The default metrics, they discard every failure
+#[derive(Debug, Default)]
+pub struct NoopErrorMetrics;
+
+impl ErrorMetrics for NoopErrorMetrics {
+    fn failure(&self, _operation: Operation, _category: ErrorCategory) {}
+
+    fn retry_budget_exhausted(&self, _operation: Operation, _category: ErrorCategory) {}
+}
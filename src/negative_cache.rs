@@ -0,0 +1,166 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use futures::future::Shared;
+use errors::{EurekaClientError, is_not_found};
+use response::ApplicationResponse;
+
+type Outcome = Rc<Result<ApplicationResponse, EurekaClientError>>;
+type Lookup = Shared<Box<Future<Item=Outcome, Error=()>>>;
+
+/// Remembers applications eureka answered a 404 for, and merges concurrent lookups of the same
+/// application into a single request
+pub(crate) struct NegativeCache {
+    ttl: Duration,
+    missing: RefCell<HashMap<String, Instant>>,
+    in_flight: RefCell<HashMap<String, Lookup>>
+}
+
+impl NegativeCache {
+    pub(crate) fn new(ttl: Duration) -> NegativeCache {
+        NegativeCache {
+            ttl: ttl,
+            missing: RefCell::new(HashMap::new()),
+            in_flight: RefCell::new(HashMap::new())
+        }
+    }
+
+    /// Whether the application was missing less than a ttl ago
+    pub(crate) fn is_missing(&self, application_id: &str) -> bool {
+        let mut missing = self.missing.borrow_mut();
+        let expired = match missing.get(application_id) {
+            Some(since) => since.elapsed() >= self.ttl,
+            None => return false
+        };
+        if expired {
+            missing.remove(application_id);
+        }
+        !expired
+    }
+
+    pub(crate) fn remember(&self, application_id: &str) {
+        self.missing.borrow_mut().insert(application_id.to_owned(), Instant::now());
+    }
+
+    pub(crate) fn clear(&self, application_id: &str) {
+        self.missing.borrow_mut().remove(application_id);
+    }
+
+    /// Looks an application up, answering from the cache or joining a lookup already in flight
+    /// where possible and calling `fetch` otherwise
+    pub(crate) fn lookup<F>(cache: &Rc<NegativeCache>, application_id: &str, fetch: F)
+                            -> Box<Future<Item=ApplicationResponse, Error=EurekaClientError>>
+        where F: FnOnce() -> Box<Future<Item=ApplicationResponse, Error=EurekaClientError>> {
+        if cache.is_missing(application_id) {
+            debug!("get_application: {} was not found less than {:?} ago", application_id, cache.ttl);
+            return Box::new(future::err(EurekaClientError::NotFound));
+        }
+
+        let existing = cache.in_flight.borrow().get(application_id).cloned();
+        let lookup = match existing {
+            Some(lookup) => {
+                debug!("get_application: joining the lookup of {} in flight", application_id);
+                lookup
+            }
+            None => {
+                let owner = cache.clone();
+                let id = application_id.to_owned();
+                let request: Box<Future<Item=Outcome, Error=()>> = Box::new(fetch().then(move |result| {
+                    owner.in_flight.borrow_mut().remove(&id);
+                    match result {
+                        Err(ref e) if is_not_found(e) => owner.remember(&id),
+                        Ok(_) => owner.clear(&id),
+                        Err(_) => ()
+                    }
+                    Ok(Rc::new(result))
+                }));
+                let lookup = request.shared();
+                cache.in_flight.borrow_mut().insert(application_id.to_owned(), lookup.clone());
+                lookup
+            }
+        };
+
+        Box::new(lookup.then(|outcome| match outcome {
+            Ok(outcome) => match **outcome {
+                Ok(ref application) => Ok(application.clone()),
+                Err(ref e) => Err(e.duplicate())
+            },
+            Err(_) => Err(EurekaClientError::GenericError("the shared lookup failed".to_owned()))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::thread;
+    use futures::sync::oneshot;
+
+    fn counting_fetch(calls: &Rc<Cell<usize>>, error: EurekaClientError)
+                      -> Box<Future<Item=ApplicationResponse, Error=EurekaClientError>> {
+        calls.set(calls.get() + 1);
+        Box::new(future::err(error))
+    }
+
+    #[test]
+    fn test_not_found_is_remembered_until_expiry() {
+        let cache = Rc::new(NegativeCache::new(Duration::from_millis(50)));
+        let calls = Rc::new(Cell::new(0));
+        for _ in 0..3 {
+            match NegativeCache::lookup(&cache, "MISSING", || counting_fetch(&calls, EurekaClientError::NotFound)).wait() {
+                Err(EurekaClientError::NotFound) => (),
+                other => panic!("expected NotFound, got {:?}", other)
+            }
+        }
+        assert_eq!(1, calls.get());
+
+        thread::sleep(Duration::from_millis(60));
+        assert!(!cache.is_missing("MISSING"));
+        let _ = NegativeCache::lookup(&cache, "MISSING", || counting_fetch(&calls, EurekaClientError::NotFound)).wait();
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_other_errors_are_not_remembered() {
+        let cache = Rc::new(NegativeCache::new(Duration::from_secs(60)));
+        let calls = Rc::new(Cell::new(0));
+        for _ in 0..2 {
+            let _ = NegativeCache::lookup(&cache, "FLAKY", || counting_fetch(&calls, EurekaClientError::InternalServerError(500))).wait();
+        }
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn test_concurrent_lookups_share_one_request() {
+        let cache = Rc::new(NegativeCache::new(Duration::from_secs(60)));
+        let calls = Rc::new(Cell::new(0));
+        let (tx, rx) = oneshot::channel::<()>();
+        let fetch_calls = calls.clone();
+        let first = NegativeCache::lookup(&cache, "SLOW", move || {
+            fetch_calls.set(fetch_calls.get() + 1);
+            Box::new(rx.then(|_| Err(EurekaClientError::NotFound)))
+        });
+        let others: Vec<_> = (0..4)
+            .map(|_| NegativeCache::lookup(&cache, "SLOW", || counting_fetch(&calls, EurekaClientError::NotFound)))
+            .collect();
+        tx.send(()).unwrap();
+
+        let all = future::join_all(others.into_iter().chain(Some(first)).map(|f| f.then(|r| Ok::<_, ()>(r))));
+        let results = all.wait().unwrap();
+        assert_eq!(1, calls.get());
+        assert!(results.iter().all(|r| match *r { Err(ref e) => is_not_found(e), Ok(_) => false }));
+        assert!(cache.is_missing("SLOW"));
+    }
+
+    #[test]
+    fn test_clear() {
+        let cache = NegativeCache::new(Duration::from_secs(60));
+        cache.remember("BACK");
+        assert!(cache.is_missing("BACK"));
+        cache.clear("BACK");
+        assert!(!cache.is_missing("BACK"));
+    }
+}
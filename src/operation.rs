@@ -0,0 +1,47 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+const REGISTER: &'static str = "REGISTER";
+const DEREGISTER: &'static str = "DEREGISTER";
+const HEARTBEAT: &'static str = "HEARTBEAT";
+const STATUS_OVERRIDE: &'static str = "STATUS_OVERRIDE";
+const METADATA_UPDATE: &'static str = "METADATA_UPDATE";
+const QUERY: &'static str = "QUERY";
+
+/// The kinds of calls a client makes against eureka, see
+/// [EurekaClient::set_allowed_operations](struct.EurekaClient.html#method.set_allowed_operations)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operation {
+    Register,
+    Deregister,
+    Heartbeat,
+    /// Setting and deleting status overrides
+    StatusOverride,
+    MetadataUpdate,
+    /// Every read, from single instances to the whole registry and the server health
+    Query
+}
+
+impl Operation {
+    pub fn all() -> BTreeSet<Operation> {
+        use self::Operation::*;
+        vec![Register, Deregister, Heartbeat, StatusOverride, MetadataUpdate, Query].into_iter().collect()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Operation::Register => REGISTER,
+            Operation::Deregister => DEREGISTER,
+            Operation::Heartbeat => HEARTBEAT,
+            Operation::StatusOverride => STATUS_OVERRIDE,
+            Operation::MetadataUpdate => METADATA_UPDATE,
+            Operation::Query => QUERY
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
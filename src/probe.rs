@@ -0,0 +1,61 @@
+//! Probing the health endpoints of discovered instances
+//!
+//! See [EurekaClient::probe_health](../struct.EurekaClient.html#method.probe_health). Probes never
+//! send the credentials configured for Eureka, the instances are a different audience.
+use response::Instance;
+
+const FALLBACK_HEALTH_PATH: &'static str = "/health";
+
+/// The outcome of probing a single instance
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProbeResult {
+    /// The instance answered with a 2xx
+    Healthy,
+    /// The instance answered with any other status
+    Unhealthy { status: u16 },
+    /// The instance could not be reached or did not answer in time
+    Unreachable { error: String },
+    /// The instance advertises no health check url, status page url or port
+    NoUrl
+}
+
+impl ProbeResult {
+    pub fn is_healthy(&self) -> bool {
+        *self == ProbeResult::Healthy
+    }
+}
+
+/// The url an instance is probed at
+///
+/// This is the health check url, then the status page url, then `/health` on the instance's
+/// host name and port.
+pub(crate) fn probe_url(instance: &Instance) -> Option<String> {
+    if !instance.health_check_url.is_empty() {
+        return Some(instance.health_check_url.clone());
+    }
+    if !instance.status_page_url.is_empty() {
+        return Some(instance.status_page_url.clone());
+    }
+    instance.port.map(|port| format!("http://{}:{}{}", instance.host_name, port, FALLBACK_HEALTH_PATH))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::instance::tests::build_test_instance;
+
+    #[test]
+    fn test_probe_url_fallbacks() {
+        let mut instance = build_test_instance();
+        assert_eq!(Some("http://washingtonpost.com".to_owned()), probe_url(&instance));
+
+        instance.health_check_url = String::new();
+        assert_eq!(Some("http://nytimes.com".to_owned()), probe_url(&instance));
+
+        instance.status_page_url = String::new();
+        assert_eq!(Some("http://Foo:80/health".to_owned()), probe_url(&instance));
+
+        instance.port = None;
+        assert_eq!(None, probe_url(&instance));
+    }
+}
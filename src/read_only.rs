@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::time::Duration;
+use futures::Future;
+use response::{ApplicationResponse, ApplicationsDeltaResponse, ApplicationsResponse, Instance, InstanceResponse, ServerHealth, SnapshotPolicy};
+use errors::EurekaClientError;
+use eureka_client::EurekaClient;
+use effective_config::EffectiveConfig;
+use call_options::CallOptions;
+use probe::ProbeResult;
+use tokio_core::reactor::Handle;
+
+/// A client that can only read from Eureka
+///
+/// The calls that change the registry do not exist on this type, so using it where only reads
+/// are allowed turns an accidental write into a compile error:
+///
+/// ```compile_fail
+/// # extern crate rust_eureka;
+/// # extern crate tokio_core;
+/// # use rust_eureka::ReadOnlyEurekaClient;
+/// # use rust_eureka::request::{InstanceBuilder, RegisterRequest};
+/// # fn main() {
+/// let core = tokio_core::reactor::Core::new().unwrap();
+/// let handle = core.handle();
+/// let client = ReadOnlyEurekaClient::new(&handle, "dashboard", "http://localhost:8761");
+/// let instance = InstanceBuilder::new("DASHBOARD", "localhost", "127.0.0.1").build().unwrap();
+/// client.register("DASHBOARD", &RegisterRequest::new(instance));
+/// # }
+/// ```
+///
+/// To configure credentials, an audit sink or a dialect, set them on an
+/// [EurekaClient](struct.EurekaClient.html) and downgrade it with `into_read_only`.
+pub struct ReadOnlyEurekaClient<'a> {
+    client: EurekaClient<'a>
+}
+
+impl<'a> ReadOnlyEurekaClient<'a> {
+    /// Creates a new read only client, see [EurekaClient::new](struct.EurekaClient.html#method.new)
+    pub fn new(handle: &'a Handle, client_name: &str, eureka_cluster_url: &str) -> ReadOnlyEurekaClient<'a> {
+        ReadOnlyEurekaClient::from(EurekaClient::new(handle, client_name, eureka_cluster_url))
+    }
+
+    pub fn effective_config(&self) -> EffectiveConfig {
+        self.client.effective_config()
+    }
+
+    /// See [EurekaClient::with_options](struct.EurekaClient.html#method.with_options)
+    pub fn with_options(&self, options: CallOptions) -> ReadOnlyEurekaClient<'a> {
+        ReadOnlyEurekaClient::from(self.client.with_options(options))
+    }
+
+    pub fn get_application(&self, application_id: &str) -> Box<Future<Item=ApplicationResponse, Error=EurekaClientError>> {
+        self.client.get_application(application_id)
+    }
+
+    /// See [EurekaClient::wait_for_application_change](struct.EurekaClient.html#method.wait_for_application_change)
+    pub fn wait_for_application_change<'b>(&'b self, application_id: &str, baseline: &ApplicationResponse, poll_interval: Duration, timeout: Duration)
+                                           -> Box<Future<Item=ApplicationResponse, Error=EurekaClientError> + 'b> {
+        self.client.wait_for_application_change(application_id, baseline, poll_interval, timeout)
+    }
+
+    pub fn get_instance(&self, application_id: &str, instance_id: &str) -> Box<Future<Item=InstanceResponse, Error=EurekaClientError>> {
+        self.client.get_instance(application_id, instance_id)
+    }
+
+    pub fn get_instance_by_id(&self, instance_id: &str) -> Box<Future<Item=InstanceResponse, Error=EurekaClientError>> {
+        self.client.get_instance_by_id(instance_id)
+    }
+
+    pub fn get_applications_in_regions(&self, regions: &[&str]) -> Box<Future<Item=ApplicationsResponse, Error=EurekaClientError>> {
+        self.client.get_applications_in_regions(regions)
+    }
+
+    pub fn get_applications_delta(&self) -> Box<Future<Item=ApplicationsDeltaResponse, Error=EurekaClientError>> {
+        self.client.get_applications_delta()
+    }
+
+    pub fn get_instances_by_vip(&self, vip_address: &str) -> Box<Future<Item=ApplicationsResponse, Error=EurekaClientError>> {
+        self.client.get_instances_by_vip(vip_address)
+    }
+
+    pub fn get_instances_by_svip(&self, svip_address: &str) -> Box<Future<Item=ApplicationsResponse, Error=EurekaClientError>> {
+        self.client.get_instances_by_svip(svip_address)
+    }
+
+    pub fn get_applications(&self) -> Box<Future<Item=ApplicationsResponse, Error=EurekaClientError>> {
+        self.client.get_applications()
+    }
+
+    /// See [EurekaClient::bootstrap_applications](struct.EurekaClient.html#method.bootstrap_applications)
+    pub fn bootstrap_applications<P: AsRef<Path>>(&self, path: P, policy: &SnapshotPolicy) -> Box<Future<Item=ApplicationsResponse, Error=EurekaClientError>> {
+        self.client.bootstrap_applications(path, policy)
+    }
+
+    /// See [EurekaClient::server_health](struct.EurekaClient.html#method.server_health)
+    pub fn server_health(&self) -> Box<Future<Item=ServerHealth, Error=EurekaClientError>> {
+        self.client.server_health()
+    }
+
+    /// See [EurekaClient::probe_health](struct.EurekaClient.html#method.probe_health)
+    pub fn probe_health(&self, instance: &Instance, timeout: Option<Duration>) -> Box<Future<Item=ProbeResult, Error=EurekaClientError>> {
+        self.client.probe_health(instance, timeout)
+    }
+
+    /// See [EurekaClient::probe_application](struct.EurekaClient.html#method.probe_application)
+    pub fn probe_application(&self, application: &ApplicationResponse, timeout: Option<Duration>, concurrency: usize)
+                             -> Box<Future<Item=Vec<(String, ProbeResult)>, Error=EurekaClientError>> {
+        self.client.probe_application(application, timeout, concurrency)
+    }
+}
+
+impl<'a> From<EurekaClient<'a>> for ReadOnlyEurekaClient<'a> {
+    fn from(client: EurekaClient<'a>) -> ReadOnlyEurekaClient<'a> {
+        ReadOnlyEurekaClient { client: client }
+    }
+}
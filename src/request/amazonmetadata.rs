@@ -1,5 +1,5 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use std::fmt;
 
 const AMI_LAUNCH_INDEX: &'static str = "ami-launch-index";
@@ -13,14 +13,11 @@ const LOCAL_IPV4: &'static str = "local-ipv4";
 const HOSTNAME: &'static str = "hostname";
 const AMI_ID: &'static str = "ami-id";
 const INSTANCE_TYPE: &'static str = "instance-type";
-const JSON_FIELDS: &'static [&'static str] = &[AMI_LAUNCH_INDEX, LOCAL_HOSTNAME, AVAILABILITY_ZONE, INSTANCE_ID,
-    PUBLIC_IPV4, PUBLIC_HOSTNAME, AMI_MANIFEST_PATH, LOCAL_IPV4,
-    HOSTNAME, AMI_ID, INSTANCE_TYPE];
 const RUST_FIELDS: &'static [&'static str] = &["ami_launch_index", "local_hostname", "availability_zone", "instance_id",
     "public_ip4", "public_hostname", "ami_manifest_path", "local_ip4", "hostname", "ami_id", "instance_type"];
 const AMAZON_META_DATA: &'static str = "AmazonMetaData";
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AmazonMetaData {
     pub ami_launch_index: String,
     pub local_hostname: String,
@@ -57,7 +54,7 @@ impl Serialize for AmazonMetaData {
 impl<'de> Deserialize<'de> for AmazonMetaData {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { AmiLaunchIndex, LocalHostname, AvailabilityZone, InstanceId, PublicIp4, PublicHostname, AmiManifestPath, LocalIp4, Hostname, AmiId, InstanceType };
+        enum Field { AmiLaunchIndex, LocalHostname, AvailabilityZone, InstanceId, PublicIp4, PublicHostname, AmiManifestPath, LocalIp4, Hostname, AmiId, InstanceType, Ignore };
 
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
@@ -85,7 +82,7 @@ impl<'de> Deserialize<'de> for AmazonMetaData {
                             HOSTNAME => Ok(Field::Hostname),
                             AMI_ID => Ok(Field::AmiId),
                             INSTANCE_TYPE => Ok(Field::InstanceType),
-                            _ => Err(DeError::unknown_field(v, JSON_FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -185,6 +182,9 @@ impl<'de> Deserialize<'de> for AmazonMetaData {
                             }
                             maybe_instance_type= Some(map.next_value()?)
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
 
@@ -1,5 +1,5 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use std::fmt;
 use super::DcName;
 use super::AmazonMetaData;
@@ -9,11 +9,12 @@ const NAME: &'static str = "name";
 const METADATA: &'static str = "metadata";
 // The eureka API has some awful cruft
 const CLASS: &'static str = "@class";
-const CLASS_VALUE: &'static str = "com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo";
+const DEFAULT_CLASS_VALUE: &'static str = "com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo";
+const AMAZON_CLASS_VALUE: &'static str = "com.netflix.appinfo.AmazonInfo";
 const DATA_CENTER_INFO: &'static str = "DataCenterInfo";
 const FIELDS: &'static [&'static str] = &[CLASS, NAME, METADATA];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DataCenterInfo {
     pub name: DcName,
     pub metadata: Option<AmazonMetaData>
@@ -22,11 +23,17 @@ pub struct DataCenterInfo {
 impl Serialize for DataCenterInfo {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
         S: Serializer {
-        let mut s = serializer.serialize_struct(DATA_CENTER_INFO, 2)?;
-        // weird netflix field
-        s.serialize_field(CLASS, CLASS_VALUE)?;
+        let mut s = serializer.serialize_struct(DATA_CENTER_INFO, 3)?;
+        // weird netflix field, the java class eureka deserializes the data center info into
+        let class = match self.name {
+            DcName::Amazon => AMAZON_CLASS_VALUE,
+            DcName::MyOwn => DEFAULT_CLASS_VALUE
+        };
+        s.serialize_field(CLASS, class)?;
         s.serialize_field(NAME, &self.name)?;
-        s.serialize_field(METADATA, &self.metadata)?;
+        if let &Some(ref metadata) = &self.metadata {
+            s.serialize_field(METADATA, metadata)?;
+        }
         s.end()
     }
 }
@@ -34,7 +41,7 @@ impl Serialize for DataCenterInfo {
 impl<'de> Deserialize<'de> for DataCenterInfo {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { Name, Metadata, Class };
+        enum Field { Name, Metadata, Class, Ignore };
 
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
@@ -54,7 +61,7 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                             NAME => Ok(Field::Name),
                             METADATA => Ok(Field::Metadata),
                             CLASS => Ok(Field::Class),
-                            _ => Err(DeError::unknown_field(v, FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -74,7 +81,7 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                 A: MapAccess<'de> {
                 let mut maybe_name = None;
                 let mut maybe_metadata = None;
-                let mut maybe_class: Option<&str> = None;
+                let mut maybe_class: Option<String> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -93,6 +100,9 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                         Field::Class => {
                             maybe_class = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
                 let name = maybe_name.ok_or_else(|| DeError::missing_field(NAME));
@@ -161,7 +171,15 @@ pub mod test {
     }
 
     fn sample_data_center() -> String {
-        format!("{{\"@class\":\"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo\",\"name\":\"Amazon\",\"metadata\":{}}}", sample_meta_data())
+        format!("{{\"@class\":\"com.netflix.appinfo.AmazonInfo\",\"name\":\"Amazon\",\"metadata\":{}}}", sample_meta_data())
+    }
+
+    #[test]
+    fn test_serialize_my_own_data_center_info() {
+        let dci = DataCenterInfo { name: DcName::MyOwn, metadata: None };
+        let json = r#"{"@class":"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo","name":"MyOwn"}"#;
+        assert_eq!(json, serde_json::to_string(&dci).unwrap());
+        assert_eq!(dci, serde_json::from_str(json).unwrap());
     }
 
 
@@ -1,16 +1,19 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use serde_json::{Map, Value};
 use std::fmt;
 use std::str::FromStr;
 use super::DataCenterInfo;
+use super::DcName;
 use super::LeaseInfo;
 use super::Status;
+use vip;
 
 // Field name constants
 const INSTANCE: &'static str = "Instance";
 const HOST_NAME: &'static str = "hostName";
 const APP: &'static str = "app";
+const APP_GROUP_NAME: &'static str = "appGroupName";
 const IP_ADDR: &'static str = "ipAddr";
 const VIP_ADDRESS: &'static str = "vipAddress";
 const SECURE_VIP_ADDRESS: &'static str = "secureVipAddress";
@@ -24,22 +27,24 @@ const DATA_CENTER_INFO: &'static str = "dataCenterInfo";
 const LEASE_INFO: &'static str = "leaseInfo";
 const METADATA: &'static str = "metadata";
 const OVERRIDDENSTATUS: &'static str = "overriddenstatus";
-const COUNTRY_ID: &'static str = "countryId";
-const JSON_FIELDS: &'static [&'static str] = &[INSTANCE, HOST_NAME, APP, IP_ADDR, VIP_ADDRESS, SECURE_VIP_ADDRESS,
-    STATUS, PORT, SECURE_PORT, HOME_PAGE_URL, STATUS_PAGE_URL, HEALTH_CHECK_URL,
-    DATA_CENTER_INFO, LEASE_INFO, METADATA, OVERRIDDENSTATUS, COUNTRY_ID];
-const RUST_FIELDS: &'static [&'static str] = &["host_name", "app", "ip_addr", "vip_address", "secure_vip_address",
+const RUST_FIELDS: &'static [&'static str] = &["host_name", "app", "app_group_name", "ip_addr", "vip_address", "secure_vip_address",
     "status", "port Option", "secure_port", "homepage_url", "status_page_url",
     "health_check_url", "data_center_info", "lease_info", "metadata", OVERRIDDENSTATUS, "country_id"];
+// The fields a registration may carry, and the ones it must
+pub(crate) const ACCEPTED_FIELDS: &'static [&'static str] = &[HOST_NAME, APP, APP_GROUP_NAME, IP_ADDR, VIP_ADDRESS, SECURE_VIP_ADDRESS,
+    STATUS, PORT, SECURE_PORT, HOME_PAGE_URL, STATUS_PAGE_URL, HEALTH_CHECK_URL, DATA_CENTER_INFO, LEASE_INFO, METADATA];
+pub(crate) const REQUIRED_FIELDS: &'static [&'static str] = &[HOST_NAME, APP, IP_ADDR, VIP_ADDRESS, SECURE_VIP_ADDRESS,
+    STATUS, HOME_PAGE_URL, STATUS_PAGE_URL, HEALTH_CHECK_URL, DATA_CENTER_INFO];
 
 const PORT_DOLLAR: &'static str = "$";
 const PORT_ENABLED: &'static str = "@enabled";
 const PORT_FIELDS: &'static [&'static str] = &[PORT_DOLLAR, PORT_ENABLED];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Instance {
     pub host_name: String,
     pub app: String,
+    pub app_group_name: Option<String>,
     pub ip_addr: String,
     pub vip_address: String,
     pub secure_vip_address: String,
@@ -54,6 +59,35 @@ pub struct Instance {
     pub metadata: Map<String, Value>
 }
 
+impl Instance {
+    /// The id eureka will know this instance by
+    ///
+    /// This is the amazon instance id for instances running in amazon, otherwise the host name.
+    pub fn id(&self) -> &str {
+        if let &Some(ref metadata) = &self.data_center_info.metadata {
+            if self.data_center_info.name == DcName::Amazon {
+                return &metadata.instance_id;
+            }
+        }
+        &self.host_name
+    }
+
+    /// The application group this instance belongs to, if any
+    pub fn app_group_name(&self) -> Option<&str> {
+        self.app_group_name.as_ref().map(|g| g.as_ref())
+    }
+
+    /// The vips this instance serves, from its comma separated vip address
+    pub fn vip_addresses(&self) -> Vec<&str> {
+        vip::split(&self.vip_address)
+    }
+
+    /// The secure vips this instance serves, from its comma separated secure vip address
+    pub fn secure_vip_addresses(&self) -> Vec<&str> {
+        vip::split(&self.secure_vip_address)
+    }
+}
+
 struct Port {
     port: u16
 }
@@ -68,7 +102,8 @@ impl Serialize for Port {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
         S: Serializer {
         let mut s = serializer.serialize_struct("Port", 2)?;
-        s.serialize_field(PORT_DOLLAR, &self.port.to_string())?;
+        // eureka writes the port as a number, but the enabled flag as a string
+        s.serialize_field(PORT_DOLLAR, &self.port)?;
         s.serialize_field(PORT_ENABLED, "true")?;
         s.end()
     }
@@ -77,7 +112,7 @@ impl Serialize for Port {
 impl<'de> Deserialize<'de> for Port {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { DollarSign, Enabled };
+        enum Field { DollarSign, Enabled, Ignore };
 
 
         impl<'de> Deserialize<'de> for Field {
@@ -96,7 +131,7 @@ impl<'de> Deserialize<'de> for Port {
                         match v {
                             PORT_DOLLAR => Ok(Field::DollarSign),
                             PORT_ENABLED => Ok(Field::Enabled),
-                            _ => Err(DeError::unknown_field(v, PORT_FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -114,7 +149,7 @@ impl<'de> Deserialize<'de> for Port {
 
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where
                 A: MapAccess<'de>, {
-                let mut maybe_dollar: Option<String> = None;
+                let mut maybe_dollar: Option<PortNumber> = None;
                 let mut maybe_enabled: Option<String> = None;
 
                 while let Some(key) = map.next_key()? {
@@ -131,11 +166,14 @@ impl<'de> Deserialize<'de> for Port {
                             }
                             maybe_enabled = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
 
                 let dollar = maybe_dollar
-                    .map(|s| u16::from_str(s.as_ref()).unwrap())
+                    .map(|p| p.0)
                     .ok_or_else(|| DeError::missing_field(PORT_DOLLAR))?;
                 maybe_enabled.ok_or_else(|| DeError::missing_field(PORT_ENABLED))?;
                 // ignore enabled
@@ -147,12 +185,53 @@ impl<'de> Deserialize<'de> for Port {
     }
 }
 
+// Older clients send the port number as a string, accept either
+struct PortNumber(u16);
+
+impl<'de> Deserialize<'de> for PortNumber {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        struct PortNumberVisitor;
+
+        impl<'de> Visitor<'de> for PortNumberVisitor {
+            type Value = PortNumber;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a port number or a string holding one")
+            }
+
+            #[allow(clippy::legacy_numeric_constants)]
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where
+                E: DeError {
+                if v > u16::max_value() as u64 {
+                    return Err(DeError::custom(format!("port {} is out of range", v)));
+                }
+                Ok(PortNumber(v as u16))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where
+                E: DeError {
+                u16::from_str(v)
+                    .map(PortNumber)
+                    .map_err(|_| DeError::custom(format!("invalid port {:?}", v)))
+            }
+        }
+
+        deserializer.deserialize_any(PortNumberVisitor)
+    }
+}
+
 impl Serialize for Instance {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
         S: Serializer {
         let mut s = serializer.serialize_struct(INSTANCE, 14)?;
         s.serialize_field(HOST_NAME, &self.host_name)?;
         s.serialize_field(APP, &self.app)?;
+
+        if let &Some(ref app_group_name) = &self.app_group_name {
+            s.serialize_field(APP_GROUP_NAME, app_group_name)?;
+        }
+
         s.serialize_field(IP_ADDR, &self.ip_addr)?;
         s.serialize_field(VIP_ADDRESS, &self.vip_address)?;
         s.serialize_field(SECURE_VIP_ADDRESS, &self.secure_vip_address)?;
@@ -191,6 +270,7 @@ impl<'de> Deserialize<'de> for Instance {
         enum Field {
             HostName,
             App,
+            AppGroupName,
             IpAddr,
             VipAddress,
             SecureVipAddress,
@@ -202,7 +282,8 @@ impl<'de> Deserialize<'de> for Instance {
             HealthCheckUrl,
             DataCenterInfo,
             LeaseInfo,
-            Metadata
+            Metadata,
+            Ignore
         }
 
         impl<'de> Deserialize<'de> for Field {
@@ -221,6 +302,7 @@ impl<'de> Deserialize<'de> for Instance {
                         match v {
                             HOST_NAME => Ok(Field::HostName),
                             APP => Ok(Field::App),
+                            APP_GROUP_NAME => Ok(Field::AppGroupName),
                             IP_ADDR => Ok(Field::IpAddr),
                             VIP_ADDRESS => Ok(Field::VipAddress),
                             SECURE_VIP_ADDRESS => Ok(Field::SecureVipAddress),
@@ -233,7 +315,7 @@ impl<'de> Deserialize<'de> for Instance {
                             DATA_CENTER_INFO => Ok(Field::DataCenterInfo),
                             LEASE_INFO => Ok(Field::LeaseInfo),
                             METADATA => Ok(Field::Metadata),
-                            _ => Err(DeError::unknown_field(v, JSON_FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -255,6 +337,7 @@ impl<'de> Deserialize<'de> for Instance {
                 A: MapAccess<'de> {
                 let mut maybe_host_name = None;
                 let mut maybe_app = None;
+                let mut maybe_app_group_name = None;
                 let mut maybe_ip_addr = None;
                 let mut maybe_vip_address = None;
                 let mut maybe_secure_vip_address = None;
@@ -282,6 +365,12 @@ impl<'de> Deserialize<'de> for Instance {
                             }
                             maybe_app = Some(map.next_value()?);
                         },
+                        Field::AppGroupName => {
+                            if maybe_app_group_name.is_some() {
+                                return Err(DeError::duplicate_field(APP_GROUP_NAME));
+                            }
+                            maybe_app_group_name = Some(map.next_value()?);
+                        },
                         Field::IpAddr => {
                             if maybe_ip_addr.is_some() {
                                 return Err(DeError::duplicate_field(IP_ADDR));
@@ -354,6 +443,9 @@ impl<'de> Deserialize<'de> for Instance {
                             }
                             maybe_host_name = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
 
@@ -372,6 +464,7 @@ impl<'de> Deserialize<'de> for Instance {
                 Ok(Instance {
                     host_name: host_name?,
                     app: app?,
+                    app_group_name: maybe_app_group_name,
                     ip_addr: ip_addr?,
                     vip_address: vip_address?,
                     secure_vip_address: secure_vip_address?,
@@ -395,7 +488,6 @@ impl<'de> Deserialize<'de> for Instance {
 pub mod tests {
     use super::*;
     use serde_json;
-    use super::super::DcName;
     use super::super::AmazonMetaData;
 
     #[test]
@@ -420,6 +512,54 @@ pub mod tests {
         assert_eq!(instance, result);
     }
 
+    #[test]
+    fn test_app_group_name_round_trip() {
+        let json = build_test_instance_json()
+            .replace("\"app\":\"Bar\",", "\"app\":\"Bar\",\"appGroupName\":\"BATCH\",");
+        let instance: Instance = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some("BATCH"), instance.app_group_name());
+        assert_eq!(json, serde_json::to_string(&instance).unwrap());
+    }
+
+    #[test]
+    fn test_full_round_trip() {
+        let mut instance = build_test_instance();
+        instance.app_group_name = Some("BATCH".to_owned());
+        let json = serde_json::to_string(&instance).unwrap();
+        for key in &[STATUS_PAGE_URL, HEALTH_CHECK_URL, DATA_CENTER_INFO, LEASE_INFO, SECURE_PORT, METADATA] {
+            assert!(json.contains(&format!("\"{}\":", key)), "{} is not serialized", key);
+        }
+        assert_eq!(instance, serde_json::from_str(&json).unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_home_page_url() {
+        let json = build_test_instance_json()
+            .replace("\"homePageUrl\":\"http://google.com\",", "\"homePageUrl\":\"http://google.com\",\"homePageUrl\":\"http://bing.com\",");
+        let error = serde_json::from_str::<Instance>(&json).unwrap_err();
+        assert!(error.to_string().contains("duplicate field `homePageUrl`"), "{}", error);
+    }
+
+    #[test]
+    fn test_port_as_string() {
+        let json = build_test_instance_json()
+            .replace("\"$\":80,", "\"$\":\"80\",");
+        let instance: Instance = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some(80), instance.port);
+
+        let json = build_test_instance_json().replace("\"$\":80,", "\"$\":\"eighty\",");
+        assert!(serde_json::from_str::<Instance>(&json).is_err());
+    }
+
+    #[test]
+    fn test_id() {
+        let mut instance = build_test_instance();
+        assert_eq!("instance1a", instance.id());
+
+        instance.data_center_info.name = DcName::MyOwn;
+        assert_eq!("Foo", instance.id());
+    }
+
     pub fn build_test_instance_json() -> String {
         r#"{
            "hostName": "Foo",
@@ -428,12 +568,12 @@ pub mod tests {
            "vipAddress": "127.0.0.1",
            "secureVipAddress": "127.0.0.2",
            "status": "UP",
-           "port": { "$": "80", "@enabled": "true" },
-           "securePort": { "$": "443", "@enabled": "true" },
+           "port": { "$": 80, "@enabled": "true" },
+           "securePort": { "$": 443, "@enabled": "true" },
            "homePageUrl": "http://google.com",
            "statusPageUrl": "http://nytimes.com",
            "healthCheckUrl": "http://washingtonpost.com",
-           "dataCenterInfo": { "@class": "com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo", "name":"Amazon","metadata":
+           "dataCenterInfo": { "@class": "com.netflix.appinfo.AmazonInfo", "name":"Amazon","metadata":
            {
                 "ami-launch-index": "001a",
                 "local-hostname": "localhost0",
@@ -461,6 +601,7 @@ pub mod tests {
         Instance {
             host_name: "Foo".to_string(),
             app: "Bar".to_string(),
+            app_group_name: None,
             ip_addr: "3.128.2.12".to_string(),
             vip_address: "127.0.0.1".to_string(),
             secure_vip_address: "127.0.0.2".to_string(),
@@ -0,0 +1,506 @@
+use serde_json::{self, Map, Value};
+use super::{Instance, Status, DataCenterInfo, DcName, LeaseInfo, NamingPolicy, RegisterRequest, RegistrationLimits, ValidationError};
+use super::strip_url_parts;
+use metadata::MetadataExt;
+use vip;
+
+/// Builds an [Instance](struct.Instance.html) for registration
+///
+/// Only the application, host name and ip address are required up front,
+/// everything else falls back to a sensible default. Instances start out as
+/// STARTING, set the status to UP once they are ready for traffic. The instance is checked
+/// against the [RegistrationLimits](struct.RegistrationLimits.html) and has the
+/// [NamingPolicy](struct.NamingPolicy.html) applied when it is built.
+#[derive(Debug, Clone)]
+pub struct InstanceBuilder {
+    instance: Instance,
+    limits: RegistrationLimits,
+    naming_policy: NamingPolicy,
+    truncatable_keys: Vec<String>,
+    explicit_vip_address: bool,
+    explicit_secure_vip_address: bool,
+    allow_unknown_status: bool,
+    repair_vip_addresses: bool
+}
+
+impl InstanceBuilder {
+    /// Creates a new builder
+    ///
+    /// # Arguments
+    ///
+    /// * `app` - The name of the application this instance belongs to
+    /// * `host_name` - The host name the instance is reachable at
+    /// * `ip_addr` - The ip address the instance is reachable at
+    pub fn new(app: &str, host_name: &str, ip_addr: &str) -> InstanceBuilder {
+        InstanceBuilder {
+            instance: Instance {
+                host_name: host_name.to_owned(),
+                app: app.to_owned(),
+                app_group_name: None,
+                ip_addr: ip_addr.to_owned(),
+                vip_address: app.to_owned(),
+                secure_vip_address: app.to_owned(),
+                status: Status::Starting,
+                port: None,
+                secure_port: None,
+                homepage_url: String::new(),
+                status_page_url: String::new(),
+                health_check_url: String::new(),
+                data_center_info: DataCenterInfo {
+                    name: DcName::MyOwn,
+                    metadata: None
+                },
+                lease_info: None,
+                metadata: Map::new()
+            },
+            limits: RegistrationLimits::default(),
+            naming_policy: NamingPolicy::default(),
+            truncatable_keys: Vec::new(),
+            explicit_vip_address: false,
+            explicit_secure_vip_address: false,
+            allow_unknown_status: false,
+            repair_vip_addresses: false
+        }
+    }
+
+    /// Changes the application, the vip addresses are left as they are
+    pub fn app(mut self, app: &str) -> InstanceBuilder {
+        self.instance.app = app.to_owned();
+        self
+    }
+
+    pub fn host_name(mut self, host_name: &str) -> InstanceBuilder {
+        self.instance.host_name = host_name.to_owned();
+        self
+    }
+
+    pub fn ip_addr(mut self, ip_addr: &str) -> InstanceBuilder {
+        self.instance.ip_addr = ip_addr.to_owned();
+        self
+    }
+
+    pub fn app_group_name(mut self, app_group_name: &str) -> InstanceBuilder {
+        self.instance.app_group_name = Some(app_group_name.to_owned());
+        self
+    }
+
+    pub fn clear_app_group_name(mut self) -> InstanceBuilder {
+        self.instance.app_group_name = None;
+        self
+    }
+
+    pub fn vip_address(mut self, vip_address: &str) -> InstanceBuilder {
+        self.instance.vip_address = vip_address.to_owned();
+        self.explicit_vip_address = true;
+        self
+    }
+
+    pub fn secure_vip_address(mut self, secure_vip_address: &str) -> InstanceBuilder {
+        self.instance.secure_vip_address = secure_vip_address.to_owned();
+        self.explicit_secure_vip_address = true;
+        self
+    }
+
+    /// Sets several vip addresses for an instance serving more than one vip
+    ///
+    /// They are sent in the canonical form eureka splits, joined by commas, trimmed and
+    /// without empty addresses.
+    pub fn vip_addresses(self, vip_addresses: &[&str]) -> InstanceBuilder {
+        let vip_address = vip::join(vip_addresses);
+        self.vip_address(&vip_address)
+    }
+
+    /// Sets several secure vip addresses, see [vip_addresses](#method.vip_addresses)
+    pub fn secure_vip_addresses(self, secure_vip_addresses: &[&str]) -> InstanceBuilder {
+        let secure_vip_address = vip::join(secure_vip_addresses);
+        self.secure_vip_address(&secure_vip_address)
+    }
+
+    /// Strips url schemes, ports and paths from the vip addresses with a warning, instead of
+    /// failing the build with `VipAddressWithUrlParts`
+    pub fn repair_vip_addresses(mut self) -> InstanceBuilder {
+        self.repair_vip_addresses = true;
+        self
+    }
+
+    pub fn status(mut self, status: Status) -> InstanceBuilder {
+        self.instance.status = status;
+        self
+    }
+
+    /// Allows building an instance with the UNKNOWN status, which is otherwise rejected
+    ///
+    /// Selectors that only skip DOWN and OUT_OF_SERVICE instances route to UNKNOWN ones.
+    pub fn allow_unknown_status(mut self) -> InstanceBuilder {
+        self.allow_unknown_status = true;
+        self
+    }
+
+    pub fn port(mut self, port: u16) -> InstanceBuilder {
+        self.instance.port = Some(port);
+        self
+    }
+
+    pub fn clear_port(mut self) -> InstanceBuilder {
+        self.instance.port = None;
+        self
+    }
+
+    pub fn secure_port(mut self, secure_port: u16) -> InstanceBuilder {
+        self.instance.secure_port = Some(secure_port);
+        self
+    }
+
+    pub fn clear_secure_port(mut self) -> InstanceBuilder {
+        self.instance.secure_port = None;
+        self
+    }
+
+    pub fn homepage_url(mut self, homepage_url: &str) -> InstanceBuilder {
+        self.instance.homepage_url = homepage_url.to_owned();
+        self
+    }
+
+    pub fn status_page_url(mut self, status_page_url: &str) -> InstanceBuilder {
+        self.instance.status_page_url = status_page_url.to_owned();
+        self
+    }
+
+    pub fn health_check_url(mut self, health_check_url: &str) -> InstanceBuilder {
+        self.instance.health_check_url = health_check_url.to_owned();
+        self
+    }
+
+    pub fn data_center_info(mut self, data_center_info: DataCenterInfo) -> InstanceBuilder {
+        self.instance.data_center_info = data_center_info;
+        self
+    }
+
+    // Validates an instance that was not built here, such as one parsed from a payload, as is
+    pub(crate) fn from_instance(instance: Instance) -> InstanceBuilder {
+        InstanceBuilder {
+            instance: instance,
+            explicit_vip_address: true,
+            explicit_secure_vip_address: true,
+            ..InstanceBuilder::new("", "", "")
+        }
+    }
+
+    pub fn lease_info(mut self, lease_info: LeaseInfo) -> InstanceBuilder {
+        self.instance.lease_info = Some(lease_info);
+        self
+    }
+
+    pub fn clear_lease_info(mut self) -> InstanceBuilder {
+        self.instance.lease_info = None;
+        self
+    }
+
+    pub fn metadata(mut self, key: &str, value: &str) -> InstanceBuilder {
+        self.instance.metadata.insert(key.to_owned(), Value::String(value.to_owned()));
+        self
+    }
+
+    pub fn remove_metadata(mut self, key: &str) -> InstanceBuilder {
+        self.instance.metadata.remove(key);
+        self
+    }
+
+    /// Overrides the default size limits
+    pub fn limits(mut self, limits: RegistrationLimits) -> InstanceBuilder {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets the rules applied to the application name and vip addresses
+    pub fn naming_policy(mut self, naming_policy: NamingPolicy) -> InstanceBuilder {
+        self.naming_policy = naming_policy;
+        self
+    }
+
+    /// Allows the string value of a metadata key to be truncated to the maximum value
+    /// length instead of failing the build
+    pub fn truncate_metadata(mut self, key: &str) -> InstanceBuilder {
+        self.truncatable_keys.push(key.to_owned());
+        self
+    }
+
+    pub fn build(mut self) -> Result<Instance, ValidationError> {
+        if self.instance.status == Status::Unknown && !self.allow_unknown_status {
+            return Err(ValidationError::UnknownStatus);
+        }
+        self.apply_naming_policy()?;
+        self.check_metadata()?;
+
+        let request = RegisterRequest::new(self.instance);
+        let body_size = serde_json::to_string(&request).map(|json| json.len()).unwrap_or(0);
+        if body_size > self.limits.max_body_size {
+            return Err(ValidationError::BodyTooLarge { size: body_size, max: self.limits.max_body_size });
+        }
+        Ok(request.instance)
+    }
+
+    fn apply_naming_policy(&mut self) -> Result<(), ValidationError> {
+        let policy = &self.naming_policy;
+        let instance = &mut self.instance;
+        if let Some(vip_address) = policy.derive_vip_address(&instance.app) {
+            if !self.explicit_vip_address {
+                instance.vip_address = vip_address.clone();
+            }
+            if !self.explicit_secure_vip_address {
+                instance.secure_vip_address = vip_address;
+            }
+        }
+        instance.app = policy.normalize_app_name(&instance.app);
+        if self.repair_vip_addresses {
+            repair_vip_address(&mut instance.vip_address);
+            repair_vip_address(&mut instance.secure_vip_address);
+        }
+        policy.check_vip_address(&instance.vip_address)?;
+        policy.check_vip_address(&instance.secure_vip_address)
+    }
+
+    fn check_metadata(&mut self) -> Result<(), ValidationError> {
+        let max_length = self.limits.max_metadata_value_length;
+        let mut metadata_size = 0;
+
+        for (key, value) in self.instance.metadata.iter_mut() {
+            let length = value_length(value);
+            if length > max_length {
+                match *value {
+                    Value::String(ref mut s) if self.truncatable_keys.contains(key) => {
+                        warn!("Truncating metadata value for {:?} from {} to {} bytes", key, length, max_length);
+                        truncate(s, max_length);
+                    }
+                    _ => return Err(ValidationError::MetadataValueTooLong { key: key.clone(), length: length, max: max_length })
+                }
+            }
+            metadata_size += key.len() + value_length(value);
+        }
+
+        for keys in self.instance.metadata.case_conflicts() {
+            warn!("Metadata keys {:?} only differ by case, case-insensitive lookups will only see {:?}", keys, keys[0]);
+        }
+
+        if metadata_size > self.limits.max_metadata_size {
+            return Err(ValidationError::MetadataTooLarge { size: metadata_size, max: self.limits.max_metadata_size });
+        }
+        Ok(())
+    }
+}
+
+fn value_length(value: &Value) -> usize {
+    match *value {
+        Value::String(ref s) => s.len(),
+        ref other => other.to_string().len()
+    }
+}
+
+fn repair_vip_address(vip_address: &mut String) {
+    if let Some(repaired) = strip_url_parts(vip_address) {
+        warn!("Repairing vip address {:?} to {:?}, vip addresses are host names only", vip_address, repaired);
+        *vip_address = repaired;
+    }
+}
+
+fn truncate(s: &mut String, max: usize) {
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s.truncate(end);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use super::super::{RegisterRequest, NameCase, VipPattern};
+
+    #[test]
+    fn test_defaults() {
+        let instance = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1").build().unwrap();
+        assert_eq!("MY-APP", instance.vip_address);
+        assert_eq!(None, instance.app_group_name());
+        assert_eq!(Status::Starting, instance.status);
+        assert!(instance.metadata.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_status_needs_escape_hatch() {
+        let builder = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1").status(Status::Unknown);
+        assert_eq!(Err(ValidationError::UnknownStatus), builder.clone().build());
+        assert_eq!(Status::Unknown, builder.allow_unknown_status().build().unwrap().status);
+    }
+
+    #[test]
+    fn test_app_group_name_in_register_body() {
+        let instance = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .app_group_name("BATCH")
+            .build()
+            .unwrap();
+        assert_eq!(Some("BATCH"), instance.app_group_name());
+
+        let json = serde_json::to_string(&RegisterRequest::new(instance)).unwrap();
+        assert!(json.contains("\"appGroupName\":\"BATCH\""));
+    }
+
+    #[test]
+    fn test_app_group_name_omitted_when_unset() {
+        let instance = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1").build().unwrap();
+        let json = serde_json::to_string(&instance).unwrap();
+        assert!(!json.contains("appGroupName"));
+    }
+
+    #[test]
+    fn test_default_limits_are_finite() {
+        let limits = RegistrationLimits::default();
+        assert!(limits.max_metadata_value_length >= 1024);
+        assert!(limits.max_metadata_size >= limits.max_metadata_value_length);
+        assert!(limits.max_body_size >= limits.max_metadata_size);
+
+        let blob = "a".repeat(2 * 1024 * 1024);
+        let result = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .metadata("blob", &blob)
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_metadata_value_too_long() {
+        let result = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .limits(small_limits())
+            .metadata("blob", "0123456789abcdef")
+            .build();
+        assert_eq!(Err(ValidationError::MetadataValueTooLong { key: "blob".to_owned(), length: 16, max: 10 }), result);
+    }
+
+    #[test]
+    fn test_metadata_too_large() {
+        let result = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .limits(small_limits())
+            .metadata("a", "0123456789")
+            .metadata("b", "0123456789")
+            .build();
+        assert_eq!(Err(ValidationError::MetadataTooLarge { size: 22, max: 20 }), result);
+    }
+
+    #[test]
+    fn test_body_too_large() {
+        let limits = RegistrationLimits { max_body_size: 100, ..RegistrationLimits::default() };
+        let result = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .limits(limits)
+            .build();
+        match result {
+            Err(ValidationError::BodyTooLarge { max: 100, .. }) => (),
+            other => panic!("expected BodyTooLarge, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_truncation_whitelist() {
+        let instance = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .limits(small_limits())
+            .truncate_metadata("build")
+            .metadata("build", "0123456789abcdef")
+            .build()
+            .unwrap();
+        assert_eq!(Some(&Value::String("0123456789".to_owned())), instance.metadata.get("build"));
+    }
+
+    #[test]
+    fn test_truncation_respects_char_boundaries() {
+        let instance = InstanceBuilder::new("MY-APP", "localhost", "127.0.0.1")
+            .limits(small_limits())
+            .truncate_metadata("name")
+            .metadata("name", "ééééééé")
+            .build()
+            .unwrap();
+        assert_eq!(Some(&Value::String("ééééé".to_owned())), instance.metadata.get("name"));
+    }
+
+    #[test]
+    fn test_naming_policy() {
+        let policy = NamingPolicy {
+            app_name_case: NameCase::Upper,
+            vip_pattern: VipPattern::Rfc1123,
+            vip_template: Some("{app}.internal".to_owned())
+        };
+        let instance = InstanceBuilder::new("my-app", "localhost", "127.0.0.1")
+            .naming_policy(policy.clone())
+            .secure_vip_address("my-app.secure")
+            .build()
+            .unwrap();
+        assert_eq!("MY-APP", instance.app);
+        assert_eq!("my-app.internal", instance.vip_address);
+        assert_eq!("my-app.secure", instance.secure_vip_address);
+
+        let result = InstanceBuilder::new("my-app", "localhost", "127.0.0.1")
+            .naming_policy(policy)
+            .vip_address("My_App")
+            .build();
+        assert_eq!(Err(ValidationError::InvalidVipAddress { value: "My_App".to_owned() }), result);
+    }
+
+    #[test]
+    fn test_vip_address_with_url_parts_is_rejected() {
+        let result = InstanceBuilder::new("orders", "localhost", "127.0.0.1")
+            .vip_address("orders")
+            .secure_vip_address("https://orders:8443")
+            .build();
+        assert_eq!(Err(ValidationError::VipAddressWithUrlParts { value: "https://orders:8443".to_owned(),
+                                                                 repaired: "orders".to_owned() }), result);
+    }
+
+    #[test]
+    fn test_vip_address_with_url_parts_is_repaired() {
+        let instance = InstanceBuilder::new("orders", "localhost", "127.0.0.1")
+            .vip_address("http://orders:8080,orders-v2")
+            .secure_vip_address("https://orders:8443")
+            .repair_vip_addresses()
+            .build()
+            .unwrap();
+        assert_eq!("orders,orders-v2", instance.vip_address);
+        assert_eq!("orders", instance.secure_vip_address);
+    }
+
+    #[test]
+    fn test_multiple_vip_addresses() {
+        let instance = InstanceBuilder::new("orders", "localhost", "127.0.0.1")
+            .vip_addresses(&["orders", " orders-canary ", ""])
+            .secure_vip_addresses(&["orders-secure, ,orders-canary-secure"])
+            .build()
+            .unwrap();
+        assert_eq!("orders,orders-canary", instance.vip_address);
+        assert_eq!(vec!["orders", "orders-canary"], instance.vip_addresses());
+        assert_eq!("orders-secure,orders-canary-secure", instance.secure_vip_address);
+        assert_eq!(vec!["orders-secure", "orders-canary-secure"], instance.secure_vip_addresses());
+    }
+
+    #[test]
+    fn test_default_naming_policy_keeps_app_as_vip() {
+        let instance = InstanceBuilder::new("My_App", "localhost", "127.0.0.1").build().unwrap();
+        assert_eq!("My_App", instance.app);
+        assert_eq!("My_App", instance.vip_address);
+        assert_eq!("My_App", instance.secure_vip_address);
+    }
+
+    #[test]
+    fn test_keys_differing_by_case_only_warn() {
+        let instance = InstanceBuilder::new("My_App", "localhost", "127.0.0.1")
+            .metadata("zone", "primary")
+            .metadata("Zone", "backup")
+            .build()
+            .unwrap();
+        assert_eq!(vec![vec!["Zone", "zone"]], instance.metadata.case_conflicts());
+        assert_eq!(Some(&Value::String("primary".to_owned())), instance.metadata.get_ci("zone"));
+    }
+
+    fn small_limits() -> RegistrationLimits {
+        RegistrationLimits {
+            max_metadata_value_length: 10,
+            max_metadata_size: 20,
+            max_body_size: 4096
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use super::{Instance, InstanceBuilder, ValidationError};
+
+/// A starting point for registering many near identical instances
+///
+/// Each instance starts out as a copy of everything set on the template's
+/// [InstanceBuilder](struct.InstanceBuilder.html), including data center info, lease info and
+/// limits, then has its overrides applied. Overrides can clear inherited optional fields with
+/// the builder's `clear_*` and `remove_metadata` methods.
+#[derive(Debug, Clone)]
+pub struct InstanceTemplate {
+    builder: InstanceBuilder
+}
+
+impl InstanceTemplate {
+    pub fn new(builder: InstanceBuilder) -> InstanceTemplate {
+        InstanceTemplate {
+            builder: builder
+        }
+    }
+
+    /// Builds a new instance from the template with the overrides applied
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - Receives a builder holding the template's settings
+    pub fn instantiate<F>(&self, overrides: F) -> Result<Instance, ValidationError>
+        where F: FnOnce(InstanceBuilder) -> InstanceBuilder {
+        overrides(self.builder.clone()).build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use super::super::{DataCenterInfo, DcName, LeaseInfo, RegistrationLimits};
+    use super::super::instance::tests::build_test_instance;
+
+    fn amazon() -> DataCenterInfo {
+        build_test_instance().data_center_info
+    }
+
+    fn worker_template() -> InstanceTemplate {
+        InstanceTemplate::new(InstanceBuilder::new("WORKER", "batch01", "10.0.0.1")
+            .app_group_name("BATCH")
+            .port(8080)
+            .data_center_info(amazon())
+            .lease_info(LeaseInfo { eviction_duration_in_secs: Some(30) })
+            .metadata("queue", "default")
+            .metadata("owner", "batch-team"))
+    }
+
+    #[test]
+    fn test_inherits_everything() {
+        let instance = worker_template().instantiate(|b| b).unwrap();
+        assert_eq!("WORKER", instance.app);
+        assert_eq!(Some("BATCH"), instance.app_group_name());
+        assert_eq!(Some(8080), instance.port);
+        assert_eq!(amazon(), instance.data_center_info);
+        assert_eq!(Some(LeaseInfo { eviction_duration_in_secs: Some(30) }), instance.lease_info);
+        assert_eq!(2, instance.metadata.len());
+    }
+
+    #[test]
+    fn test_overrides() {
+        let template = worker_template();
+        let instance = template.instantiate(|b| b.app("WORKER-7").port(8087).metadata("queue", "seven")).unwrap();
+        assert_eq!("WORKER-7", instance.app);
+        assert_eq!(Some(8087), instance.port);
+        assert_eq!(Some(&Value::String("seven".to_owned())), instance.metadata.get("queue"));
+        assert_eq!(Some(&Value::String("batch-team".to_owned())), instance.metadata.get("owner"));
+
+        // the template itself is left alone
+        let other = template.instantiate(|b| b).unwrap();
+        assert_eq!("WORKER", other.app);
+        assert_eq!(Some(8080), other.port);
+    }
+
+    #[test]
+    fn test_clearing_inherited_fields() {
+        let instance = worker_template()
+            .instantiate(|b| b.clear_app_group_name().clear_port().clear_lease_info().remove_metadata("owner"))
+            .unwrap();
+        assert_eq!(None, instance.app_group_name());
+        assert_eq!(None, instance.port);
+        assert_eq!(None, instance.lease_info);
+        assert_eq!(None, instance.metadata.get("owner"));
+        assert_eq!(1, instance.metadata.len());
+    }
+
+    #[test]
+    fn test_overrides_are_validated() {
+        let template = InstanceTemplate::new(InstanceBuilder::new("WORKER", "batch01", "10.0.0.1")
+            .data_center_info(DataCenterInfo { name: DcName::MyOwn, metadata: None })
+            .limits(RegistrationLimits { max_metadata_value_length: 4, ..RegistrationLimits::default() }));
+        assert!(template.instantiate(|b| b.metadata("queue", "too long")).is_err());
+    }
+}
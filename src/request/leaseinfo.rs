@@ -1,12 +1,13 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use std::fmt;
+use keys::DEFAULT_EVICTION_DURATION_IN_SECS;
 
 const LEASE_INFO: &'static str = "LeaseInfo";
 const EVICTION_DURATION_IN_SECS: &'static str = "evictionDurationInSecs";
 const FIELDS: &'static [&'static str] = &[EVICTION_DURATION_IN_SECS];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LeaseInfo {
     pub eviction_duration_in_secs: Option<u32>
 }
@@ -16,7 +17,7 @@ impl Serialize for LeaseInfo {
         S: Serializer {
         let mut s = serializer.serialize_struct(LEASE_INFO, 1)?;
         // if not specified we will serialize the default of 90
-        let result = self.eviction_duration_in_secs.unwrap_or(90);
+        let result = self.eviction_duration_in_secs.unwrap_or(DEFAULT_EVICTION_DURATION_IN_SECS);
         s.serialize_field(EVICTION_DURATION_IN_SECS, &result)?;
         s.end()
     }
@@ -25,7 +26,7 @@ impl Serialize for LeaseInfo {
 impl<'de> Deserialize<'de> for LeaseInfo {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { EvictionDurationInSecs };
+        enum Field { EvictionDurationInSecs, Ignore };
 
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
@@ -44,7 +45,7 @@ impl<'de> Deserialize<'de> for LeaseInfo {
 
                         match v {
                             EVICTION_DURATION_IN_SECS => Ok(Field::EvictionDurationInSecs),
-                            _ => Err(DeError::unknown_field(v, FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
 
@@ -73,6 +74,9 @@ impl<'de> Deserialize<'de> for LeaseInfo {
                             }
                             maybe_eviction_duration = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
                 Ok(LeaseInfo{
@@ -4,7 +4,12 @@ mod amazonmetadata;
 mod datacenterinfo;
 mod leaseinfo;
 mod instance;
+mod instance_builder;
+mod instance_template;
+mod naming;
 mod register;
+mod transformer;
+mod validation;
 
 pub use self::status::Status;
 pub use self::dcname::DcName;
@@ -12,4 +17,11 @@ pub use self::amazonmetadata::AmazonMetaData;
 pub use self::datacenterinfo::DataCenterInfo;
 pub use self::leaseinfo::LeaseInfo;
 pub use self::instance::Instance;
+pub use self::instance_builder::InstanceBuilder;
+pub use self::instance_template::InstanceTemplate;
+pub use self::naming::{NamingPolicy, NameCase, VipPattern};
 pub use self::register::RegisterRequest;
+pub use self::transformer::PayloadTransformer;
+pub use self::validation::{RegistrationLimits, ValidationError};
+pub(crate) use self::instance::{ACCEPTED_FIELDS, REQUIRED_FIELDS};
+pub(crate) use self::naming::strip_url_parts;
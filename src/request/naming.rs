@@ -0,0 +1,219 @@
+use super::ValidationError;
+
+const APP_PLACEHOLDER: &'static str = "{app}";
+const MAX_LABEL_LENGTH: usize = 63;
+const MAX_NAME_LENGTH: usize = 253;
+
+/// How the case of application names is normalized
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameCase {
+    /// Leave the name as it is
+    Preserve,
+    /// Uppercase the name, the way eureka stores it
+    Upper,
+    /// Lowercase the name
+    Lower
+}
+
+/// Which vip addresses are accepted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VipPattern {
+    /// Any vip address, eureka treats them as opaque
+    Permissive,
+    /// Every comma separated address must be a lowercase RFC-1123 host name
+    Rfc1123
+}
+
+/// Rules applied to application names and vip addresses when an instance is built
+///
+/// The default policy changes nothing. Whatever the policy, vip addresses that include a url
+/// scheme or port, as in `http://my-service:8080`, are rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamingPolicy {
+    pub app_name_case: NameCase,
+    pub vip_pattern: VipPattern,
+    /// Derives vip addresses that were not set explicitly from the application name,
+    /// `{app}` is replaced by the lowercased application name, as in `{app}.internal`
+    pub vip_template: Option<String>
+}
+
+impl Default for NamingPolicy {
+    fn default() -> Self {
+        NamingPolicy {
+            app_name_case: NameCase::Preserve,
+            vip_pattern: VipPattern::Permissive,
+            vip_template: None
+        }
+    }
+}
+
+impl NamingPolicy {
+    /// Applies the name case to an application name
+    pub fn normalize_app_name(&self, app: &str) -> String {
+        match self.app_name_case {
+            NameCase::Preserve => app.to_owned(),
+            NameCase::Upper => app.to_uppercase(),
+            NameCase::Lower => app.to_lowercase()
+        }
+    }
+
+    /// The vip address derived from an application name, if there is a template
+    pub fn derive_vip_address(&self, app: &str) -> Option<String> {
+        self.vip_template.as_ref().map(|template| template.replace(APP_PLACEHOLDER, &app.to_lowercase()))
+    }
+
+    /// Checks a vip address against the vip pattern
+    pub fn check_vip_address(&self, vip_address: &str) -> Result<(), ValidationError> {
+        if let Some(repaired) = strip_url_parts(vip_address) {
+            return Err(ValidationError::VipAddressWithUrlParts { value: vip_address.to_owned(), repaired: repaired });
+        }
+        match self.vip_pattern {
+            VipPattern::Permissive => Ok(()),
+            VipPattern::Rfc1123 if vip_address.split(',').all(|vip| is_rfc1123(vip.trim())) => Ok(()),
+            VipPattern::Rfc1123 => Err(ValidationError::InvalidVipAddress { value: vip_address.to_owned() })
+        }
+    }
+
+    /// Checks that no two application names become the same once normalized
+    ///
+    /// Use this before registering a batch of applications.
+    pub fn check_collisions(&self, app_names: &[&str]) -> Result<(), ValidationError> {
+        let mut seen: Vec<(String, &str)> = Vec::new();
+        for app in app_names {
+            let normalized = self.normalize_app_name(app);
+            if let Some(&(_, first)) = seen.iter().find(|&&(ref n, raw)| *n == normalized && raw != *app) {
+                return Err(ValidationError::NameCollision {
+                    first: first.to_owned(),
+                    second: (*app).to_owned(),
+                    normalized: normalized
+                });
+            }
+            seen.push((normalized, app));
+        }
+        Ok(())
+    }
+}
+
+/// The vip address without the url schemes, ports and paths some of its comma separated
+/// addresses include, `None` when there are none
+pub(crate) fn strip_url_parts(vip_address: &str) -> Option<String> {
+    let addresses: Vec<&str> = vip_address.split(',').collect();
+    let stripped: Vec<&str> = addresses.iter().map(|address| strip_address(address)).collect();
+    if stripped == addresses {
+        None
+    } else {
+        Some(stripped.iter().map(|address| address.trim()).collect::<Vec<&str>>().join(","))
+    }
+}
+
+fn strip_address(address: &str) -> &str {
+    let mut host = match address.find("://") {
+        Some(at) => &address[at + 3..],
+        None => address
+    };
+    if let Some(at) = host.find('/') {
+        host = &host[..at];
+    }
+    match host.rfind(':') {
+        Some(at) if !host.contains(']') && host[at + 1..].chars().all(|c| c.is_ascii_digit()) => &host[..at],
+        _ => host
+    }
+}
+
+fn is_rfc1123(name: &str) -> bool {
+    !name.is_empty() && name.len() <= MAX_NAME_LENGTH && name.split('.').all(is_label)
+}
+
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_LABEL_LENGTH
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict() -> NamingPolicy {
+        NamingPolicy {
+            app_name_case: NameCase::Upper,
+            vip_pattern: VipPattern::Rfc1123,
+            vip_template: Some("{app}.internal".to_owned())
+        }
+    }
+
+    #[test]
+    fn test_default_changes_nothing() {
+        let policy = NamingPolicy::default();
+        assert_eq!("My_App", policy.normalize_app_name("My_App"));
+        assert_eq!(None, policy.derive_vip_address("My_App"));
+        assert!(policy.check_vip_address("Not A Host!").is_ok());
+        assert!(policy.check_collisions(&["my-app", "MY-APP"]).is_ok());
+    }
+
+    #[test]
+    fn test_url_parts_are_detected() {
+        let table = vec![
+            ("my-service", None),
+            ("my-service,other", None),
+            ("Not A Host!", None),
+            ("http://my-service:8080", Some("my-service")),
+            ("https://my-service", Some("my-service")),
+            ("my-service:8080", Some("my-service")),
+            ("my-service:", Some("my-service")),
+            ("my-service/health", Some("my-service")),
+            ("a, http://b:80/api ,c", Some("a,b,c"))
+        ];
+        for (vip, expected) in table {
+            assert_eq!(expected.map(|e: &str| e.to_owned()), strip_url_parts(vip), "{:?}", vip);
+        }
+    }
+
+    #[test]
+    fn test_url_parts_are_rejected_by_every_policy() {
+        for policy in &[NamingPolicy::default(), strict()] {
+            assert_eq!(Err(ValidationError::VipAddressWithUrlParts { value: "http://my-service:8080".to_owned(),
+                                                                     repaired: "my-service".to_owned() }),
+                       policy.check_vip_address("http://my-service:8080"));
+        }
+    }
+
+    #[test]
+    fn test_name_case() {
+        assert_eq!("MY-APP", strict().normalize_app_name("my-app"));
+        let lower = NamingPolicy { app_name_case: NameCase::Lower, ..NamingPolicy::default() };
+        assert_eq!("my-app", lower.normalize_app_name("My-App"));
+    }
+
+    #[test]
+    fn test_vip_template() {
+        assert_eq!(Some("my-app.internal".to_owned()), strict().derive_vip_address("MY-APP"));
+    }
+
+    #[test]
+    fn test_rfc1123_vips() {
+        let policy = strict();
+        assert!(policy.check_vip_address("my-app.internal").is_ok());
+        assert!(policy.check_vip_address("my-app, my-app-v2.internal").is_ok());
+        assert!(policy.check_vip_address("a1").is_ok());
+
+        let long_label = "a".repeat(64);
+        for &vip in &["My-App", "-app", "app-", "my_app", "app..internal", "", "a,", long_label.as_ref()] {
+            assert_eq!(Err(ValidationError::InvalidVipAddress { value: vip.to_string() }),
+                       policy.check_vip_address(vip), "{:?}", vip);
+        }
+    }
+
+    #[test]
+    fn test_collisions() {
+        let policy = strict();
+        assert!(policy.check_collisions(&["orders", "billing", "orders"]).is_ok());
+        assert_eq!(Err(ValidationError::NameCollision {
+            first: "orders".to_owned(),
+            second: "Orders".to_owned(),
+            normalized: "ORDERS".to_owned()
+        }), policy.check_collisions(&["orders", "billing", "Orders"]));
+    }
+}
@@ -4,13 +4,10 @@ use std::iter::Iterator;
 use std::fmt;
 use std::convert::From;
 
-const UP: &'static str = "UP";
-const DOWN: &'static str = "DOWN";
-const STARTING: &'static str = "STARTING";
-const OUT_OF_SERVICE: &'static str = "OUT_OF_SERVICE";
-const UNKNOWN: &'static str = "UNKNOWN";
+use keys::{STATUS_UP as UP, STATUS_DOWN as DOWN, STATUS_STARTING as STARTING,
+           STATUS_OUT_OF_SERVICE as OUT_OF_SERVICE, STATUS_UNKNOWN as UNKNOWN};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Status {
     Up,
     Down,
@@ -0,0 +1,26 @@
+use serde_json::Value;
+
+/// Rewrites outgoing payloads for servers that only mostly speak eureka
+///
+/// The typed request model stays authoritative, transforms run after it was validated and
+/// serialized, right before the request is sent, so nothing a transform does is checked
+/// again. The counterpart for responses is the
+/// [PayloadDialect](../response/trait.PayloadDialect.html). Every method passes its payload
+/// through unchanged unless overridden.
+pub trait PayloadTransformer {
+    /// Rewrites the body of a register request, the `{"instance": ...}` envelope included
+    fn transform_register(&self, value: Value) -> Value {
+        value
+    }
+
+    /// Rewrites the query parameters of a status override, `value` for setting one and none
+    /// for deleting it
+    fn transform_status_override(&self, params: Vec<(String, String)>) -> Vec<(String, String)> {
+        params
+    }
+
+    /// Rewrites the metadata pairs of a metadata update, nothing is sent when none are left
+    fn transform_metadata(&self, params: Vec<(String, String)>) -> Vec<(String, String)> {
+        params
+    }
+}
@@ -0,0 +1,86 @@
+use std::error::Error;
+use std::fmt;
+
+const DEFAULT_MAX_METADATA_VALUE_LENGTH: usize = 16 * 1024;
+const DEFAULT_MAX_METADATA_SIZE: usize = 64 * 1024;
+const DEFAULT_MAX_BODY_SIZE: usize = 256 * 1024;
+
+/// Size limits enforced when building an instance for registration
+///
+/// The defaults are generous, they exist to stop a runaway metadata value from
+/// producing registrations large enough to slow down the whole cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegistrationLimits {
+    /// The maximum length in bytes of a single metadata value
+    pub max_metadata_value_length: usize,
+    /// The maximum combined length in bytes of all metadata keys and values
+    pub max_metadata_size: usize,
+    /// The maximum length in bytes of the serialized registration body
+    pub max_body_size: usize
+}
+
+impl Default for RegistrationLimits {
+    fn default() -> Self {
+        RegistrationLimits {
+            max_metadata_value_length: DEFAULT_MAX_METADATA_VALUE_LENGTH,
+            max_metadata_size: DEFAULT_MAX_METADATA_SIZE,
+            max_body_size: DEFAULT_MAX_BODY_SIZE
+        }
+    }
+}
+
+/// Reasons an instance can be rejected when it is built
+#[derive(Debug, PartialEq)]
+pub enum ValidationError {
+    /// A metadata value is longer than the configured maximum
+    MetadataValueTooLong { key: String, length: usize, max: usize },
+    /// The metadata as a whole is larger than the configured maximum
+    MetadataTooLarge { size: usize, max: usize },
+    /// The serialized registration body is larger than the configured maximum
+    BodyTooLarge { size: usize, max: usize },
+    /// A vip address does not match the naming policy
+    InvalidVipAddress { value: String },
+    /// A vip address includes a url scheme or port, lookups by the vip address would miss it,
+    /// see [InstanceBuilder::repair_vip_addresses](struct.InstanceBuilder.html#method.repair_vip_addresses)
+    VipAddressWithUrlParts { value: String, repaired: String },
+    /// Two application names are the same once normalized by the naming policy
+    NameCollision { first: String, second: String, normalized: String },
+    /// The instance would register with the UNKNOWN status, see
+    /// [InstanceBuilder::allow_unknown_status](struct.InstanceBuilder.html#method.allow_unknown_status)
+    UnknownStatus
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationError::MetadataValueTooLong { ref key, length, max } =>
+                write!(f, "metadata value for {:?} is {} bytes, the maximum is {}", key, length, max),
+            ValidationError::MetadataTooLarge { size, max } =>
+                write!(f, "metadata is {} bytes, the maximum is {}", size, max),
+            ValidationError::BodyTooLarge { size, max } =>
+                write!(f, "registration body is {} bytes, the maximum is {}", size, max),
+            ValidationError::InvalidVipAddress { ref value } =>
+                write!(f, "vip address {:?} does not match the naming policy", value),
+            ValidationError::VipAddressWithUrlParts { ref value, ref repaired } =>
+                write!(f, "vip address {:?} includes a scheme or port, it should be {:?}", value, repaired),
+            ValidationError::NameCollision { ref first, ref second, ref normalized } =>
+                write!(f, "application names {:?} and {:?} both normalize to {:?}", first, second, normalized),
+            ValidationError::UnknownStatus =>
+                write!(f, "registering with the UNKNOWN status was not explicitly allowed")
+        }
+    }
+}
+
+impl Error for ValidationError {
+    fn description(&self) -> &str {
+        match *self {
+            ValidationError::MetadataValueTooLong { .. } => "Metadata value too long",
+            ValidationError::MetadataTooLarge { .. } => "Metadata too large",
+            ValidationError::BodyTooLarge { .. } => "Registration body too large",
+            ValidationError::InvalidVipAddress { .. } => "Invalid vip address",
+            ValidationError::VipAddressWithUrlParts { .. } => "Vip address includes a scheme or port",
+            ValidationError::NameCollision { .. } => "Application names collide",
+            ValidationError::UnknownStatus => "Unknown status"
+        }
+    }
+}
@@ -9,7 +9,7 @@ const ADDED: &'static str = "ADDED";
 const DELETED: &'static str = "DELETED";
 const MODIFIED: &'static str = "MODIFIED";
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ActionType {
     Added,
     Deleted,
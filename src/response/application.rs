@@ -1,9 +1,55 @@
 use super::Instance;
+use serde::de::{self, Deserialize, Deserializer, Visitor, MapAccess, SeqAccess};
+use serde::ser::{Serialize, Serializer, SerializeStruct};
+use std::fmt;
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Application {
     pub name: String,
-    pub instance: Instance
+    #[serde(rename = "instance", deserialize_with = "deserialize_instances_field")]
+    pub instances: Vec<Instance>
+}
+
+impl Serialize for Application {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
+        S: Serializer {
+        let mut s = serializer.serialize_struct("Application", 2)?;
+        s.serialize_field("name", &self.name)?;
+
+        // eureka sends a bare object when an application only has one instance
+        if self.instances.len() == 1 {
+            s.serialize_field("instance", &self.instances.get(0))?;
+        } else {
+            s.serialize_field("instance", &self.instances)?;
+        }
+        s.end()
+    }
+}
+
+fn deserialize_instances_field<'de, D>(de: D) -> Result<Vec<Instance>, D::Error>
+    where D: Deserializer<'de> {
+    struct InstanceOrVec;
+
+    impl<'de> Visitor<'de> for InstanceOrVec {
+        type Value = Vec<Instance>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("instance or vec")
+        }
+
+        fn visit_map<A>(self, visitor: A) -> Result<Self::Value, A::Error> where
+            A: MapAccess<'de>, {
+            let result: Result<Instance, A::Error> = Deserialize::deserialize(de::value::MapAccessDeserializer::new(visitor));
+            result.map(|i| vec![i])
+        }
+
+        fn visit_seq<A>(self, visitor: A) -> Result<Self::Value, A::Error> where
+            A: SeqAccess<'de>, {
+            Deserialize::deserialize(de::value::SeqAccessDeserializer::new(visitor))
+        }
+    }
+
+    de.deserialize_any(InstanceOrVec)
 }
 
 
@@ -20,7 +66,7 @@ mod tests {
         let name = "test_name";
         let app = Application{
             name: name.to_owned(),
-            instance: instance
+            instances: vec![instance]
         };
         let result = serde_json::to_string(&app).unwrap();
 
@@ -39,14 +85,22 @@ mod tests {
         let name = "test_name";
         let app = Application{
             name: name.to_owned(),
-            instance: instance
+            instances: vec![instance]
         };
         let result = serde_json::from_str(&json).unwrap();
         assert_eq!(app, result);
     }
 
+    #[test]
+    fn test_multiple_instances_round_trip() {
+        let json = format!("{{\"name\":\"test_name\",\"instance\":[{},{}]}}",
+                           build_test_instance_json(), build_test_instance_json());
+        let app: Application = serde_json::from_str(&json).unwrap();
+        assert_eq!(2, app.instances.len());
+        assert_eq!(json, serde_json::to_string(&app).unwrap());
+    }
+
     fn build_register_json() -> String {
         format!("{{\"name\":\"test_name\",\"instance\":{}}}", build_test_instance_json())
     }
 }
-
@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
 use super::Application;
+use super::Instance;
+use super::{InstancePage, InstancePager, SortKey};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ApplicationResponse {
     pub application: Application
 }
@@ -11,6 +14,43 @@ impl<'a> ApplicationResponse {
             application: application
         }
     }
+
+    /// Instance ids registered by more than one instance, ordered by id
+    ///
+    /// This usually means two hosts were deployed with the same instance id.
+    pub fn duplicate_instance_ids(&self) -> Vec<(String, Vec<&Instance>)> {
+        let mut by_id: BTreeMap<&str, Vec<&Instance>> = BTreeMap::new();
+        for instance in &self.application.instances {
+            by_id.entry(instance.id()).or_default().push(instance);
+        }
+        by_id.into_iter()
+            .filter(|&(_, ref instances)| instances.len() > 1)
+            .map(|(id, instances)| (id.to_owned(), instances))
+            .collect()
+    }
+
+    /// Finds the instance with the given id
+    ///
+    /// When the id is duplicated the instance with the newest lastUpdatedTimestamp is
+    /// returned, if those are equal too the first one in the response wins.
+    pub fn instance(&self, instance_id: &str) -> Option<&Instance> {
+        self.application.instances.iter()
+            .filter(|instance| instance.id() == instance_id)
+            .fold(None, |newest: Option<&Instance>, instance| match newest {
+                Some(n) if n.last_updated_timestamp >= instance.last_updated_timestamp => Some(n),
+                _ => Some(instance)
+            })
+    }
+
+    /// A pager over the instances that sorts each order only once
+    pub fn pager<'b>(&'b self) -> InstancePager<'b> {
+        InstancePager::new(self)
+    }
+
+    /// A single page of instances, use a [pager](#method.pager) when asking for more than one
+    pub fn instances_page<'b>(&'b self, offset: usize, limit: usize, sort: SortKey) -> InstancePage<'b> {
+        self.pager().instances_page(offset, limit, sort)
+    }
 }
 
 #[cfg(test)]
@@ -25,7 +65,7 @@ mod tests {
         let instance = build_test_instance();
         let application: Application = Application {
             name: "test_app".to_owned(),
-            instance: instance
+            instances: vec![instance]
         };
         let ar = ApplicationResponse::new(application);
         let result = serde_json::to_string(&ar).unwrap();
@@ -44,13 +84,62 @@ mod tests {
         let instance = build_test_instance();
         let application: Application = Application {
             name: "test_app".to_owned(),
-            instance: instance
+            instances: vec![instance]
         };
         let ar = ApplicationResponse::new(application);
         let result = serde_json::from_str(&json).unwrap();
         assert_eq!(ar, result);
     }
 
+    #[test]
+    fn test_duplicate_instance_ids() {
+        let ar = build_duplicates_response();
+        let duplicates = ar.duplicate_instance_ids();
+        assert_eq!(1, duplicates.len());
+        assert_eq!("dup", duplicates[0].0);
+        let hosts: Vec<&str> = duplicates[0].1.iter().map(|i| i.host_name.as_ref()).collect();
+        assert_eq!(vec!["host1", "host3"], hosts);
+    }
+
+    #[test]
+    fn test_no_duplicate_instance_ids() {
+        let mut ar = build_duplicates_response();
+        ar.application.instances.pop();
+        assert!(ar.duplicate_instance_ids().is_empty());
+    }
+
+    #[test]
+    fn test_instance_prefers_newest_duplicate() {
+        let ar = build_duplicates_response();
+        assert_eq!("host3", ar.instance("dup").unwrap().host_name);
+        assert_eq!("host2", ar.instance("unique").unwrap().host_name);
+        assert!(ar.instance("missing").is_none());
+    }
+
+    #[test]
+    fn test_instance_tie_keeps_first() {
+        let mut ar = build_duplicates_response();
+        ar.application.instances[2].last_updated_timestamp = ar.application.instances[0].last_updated_timestamp;
+        assert_eq!("host1", ar.instance("dup").unwrap().host_name);
+    }
+
+    fn build_duplicates_response() -> ApplicationResponse {
+        let instances = vec![("dup", "host1", 100), ("unique", "host2", 300), ("dup", "host3", 200)]
+            .into_iter()
+            .map(|(id, host, updated)| {
+                let mut instance = build_test_instance();
+                instance.instance_id = Some(id.to_owned());
+                instance.host_name = host.to_owned();
+                instance.last_updated_timestamp = updated;
+                instance
+            })
+            .collect();
+        ApplicationResponse::new(Application {
+            name: "test_app".to_owned(),
+            instances: instances
+        })
+    }
+
     fn build_application_response_json() -> String {
 
         format!("{{\"application\":{{\"name\":\"test_app\",\"instance\":{}}}}}", build_test_instance_json())
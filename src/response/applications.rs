@@ -1,17 +1,19 @@
 use super::Application;
+use super::lenient::deserialize_versions_delta;
 use serde::de::{self, Deserialize, Deserializer, Visitor, MapAccess, SeqAccess};
 use serde::ser::{Serialize, Serializer, SerializeStruct};
 use std::convert::From;
 use std::fmt;
 
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Applications {
-    #[serde(rename = "versions__delta")]
+    #[serde(rename = "versions__delta", deserialize_with = "deserialize_versions_delta")]
     pub versions_delta: i16,
     #[serde(rename = "apps__hashcode")]
     pub apps_hashcode: String,
-    #[serde(rename = "application", deserialize_with = "deserialize_applications_field")]
+    // eureka leaves the field out when there are no applications, as does serializing
+    #[serde(rename = "application", default, deserialize_with = "deserialize_applications_field")]
     pub applications: Vec<Application>
 }
 
@@ -100,6 +102,14 @@ pub mod tests {
         assert_eq!(2, result.applications.len())
     }
 
+    #[test]
+    fn test_applications_without_application_field() {
+        let result: Applications = serde_json::from_str(r#"{"versions__delta":3,"apps__hashcode":""}"#).unwrap();
+        assert!(result.applications.is_empty());
+        let empty = Applications { applications: Vec::new(), ..build_test_applications() };
+        assert_eq!(empty, serde_json::from_str(&serde_json::to_string(&empty).unwrap()).unwrap());
+    }
+
     pub fn build_test_applications() -> Applications {
         Applications {
             versions_delta: 1,
@@ -107,9 +117,11 @@ pub mod tests {
             applications: vec![
                 Application {
                     name: "INTEGRATION_TEST".to_string(),
-                    instance: Instance {
+                    instances: vec![Instance {
+                        instance_id: None,
                         host_name: "localhost".to_string(),
                         app: "INTEGRATION_TEST".to_string(),
+                        app_group_name: None,
                         ip_addr: "127.0.0.1".to_string(),
                         status: Status::Up,
                         overriddenstatus: Some(Status::Unknown),
@@ -138,7 +150,7 @@ pub mod tests {
                         last_updated_timestamp: 1503701416750,
                         last_dirty_timestamp: 1503701416457,
                         action_type: ActionType::Added
-                    }
+                    }]
                 }
             ]
         }
@@ -0,0 +1,75 @@
+use super::{ActionType, Applications, Instance};
+
+/// The changes to the registry since the last fetch, as returned by `GET /v2/apps/delta`
+///
+/// Every instance carries the `action_type` of its change. Deleted instances are listed with
+/// the last values eureka knew for them. The `apps_hashcode` is the hash of the whole registry
+/// once the delta is applied, a client whose own hash differs has missed a delta and should
+/// fetch the full registry again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplicationsDeltaResponse {
+    pub applications: Applications
+}
+
+impl ApplicationsDeltaResponse {
+    pub fn new(applications: Applications) -> ApplicationsDeltaResponse {
+        ApplicationsDeltaResponse {
+            applications: applications
+        }
+    }
+
+    /// The instances changed with the given action, in the order eureka sent them
+    pub fn instances(&self, action_type: &ActionType) -> Vec<&Instance> {
+        self.applications.applications.iter()
+            .flat_map(|app| app.instances.iter())
+            .filter(|instance| instance.action_type == *action_type)
+            .collect()
+    }
+
+    /// Whether nothing changed since the last fetch
+    pub fn is_empty(&self) -> bool {
+        self.applications.applications.iter().all(|app| app.instances.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use super::*;
+    use super::super::instance::tests::build_test_instance_json;
+
+    fn delta_json(instances: &[(&str, &str)]) -> String {
+        let instances: Vec<String> = instances.iter()
+            .map(|&(host, action)| build_test_instance_json()
+                .replace("\"hostName\":\"Foo\"", &format!("\"hostName\":\"{}\"", host))
+                .replace("\"actionType\":\"ADDED\"", &format!("\"actionType\":\"{}\"", action)))
+            .collect();
+        format!(r#"{{"applications":{{"versions__delta":7,"apps__hashcode":"UP_2_","application":[{{"name":"INTEGRATION_TEST","instance":[{}]}}]}}}}"#,
+                instances.join(","))
+    }
+
+    fn hosts(instances: Vec<&Instance>) -> Vec<&str> {
+        instances.into_iter().map(|instance| instance.host_name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_instances_by_action_type() {
+        let delta: ApplicationsDeltaResponse = serde_json::from_str(&delta_json(&[("a", "ADDED"), ("b", "MODIFIED"), ("c", "DELETED"), ("d", "ADDED")])).unwrap();
+        assert_eq!(7, delta.applications.versions_delta);
+        assert_eq!("UP_2_", delta.applications.apps_hashcode);
+        assert_eq!(vec!["a", "d"], hosts(delta.instances(&ActionType::Added)));
+        assert_eq!(vec!["b"], hosts(delta.instances(&ActionType::Modified)));
+        assert_eq!(vec!["c"], hosts(delta.instances(&ActionType::Deleted)));
+        assert!(!delta.is_empty());
+    }
+
+    #[test]
+    fn test_empty_delta() {
+        let without_field: ApplicationsDeltaResponse =
+            serde_json::from_str(r#"{"applications":{"versions__delta":8,"apps__hashcode":"UP_2_"}}"#).unwrap();
+        assert!(without_field.is_empty());
+        let empty_array: ApplicationsDeltaResponse =
+            serde_json::from_str(r#"{"applications":{"versions__delta":8,"apps__hashcode":"UP_2_","application":[]}}"#).unwrap();
+        assert_eq!(without_field, empty_array);
+    }
+}
@@ -1,6 +1,6 @@
-use super::Applications;
+use super::{Applications, Application, Instance};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ApplicationsResponse {
     pub applications: Applications
 }
@@ -11,6 +11,40 @@ impl<'a> ApplicationsResponse {
             applications: applications
         }
     }
+
+    /// Returns the applications with at least one instance in the specified app group.
+    /// Group names are compared case-insensitively.
+    pub fn applications_in_group(&self, app_group_name: &str) -> Vec<&Application> {
+        let app_group_name = app_group_name.to_lowercase();
+        self.applications.applications
+            .iter()
+            .filter(|app| app.instances.iter().any(|instance| {
+                instance.app_group_name()
+                    .map(|g| g.to_lowercase() == app_group_name)
+                    .unwrap_or(false)
+            }))
+            .collect()
+    }
+
+    /// The instances serving `vip`, the way `GET /v2/vips/{vipAddress}` finds them
+    ///
+    /// Instances serving several vips are found by any of them, see
+    /// [Instance::serves_vip](struct.Instance.html#method.serves_vip).
+    pub fn instances_by_vip(&self, vip: &str) -> Vec<&Instance> {
+        self.find_instances(|instance| instance.serves_vip(vip))
+    }
+
+    /// The instances serving the secure vip `svip`, see [instances_by_vip](#method.instances_by_vip)
+    pub fn instances_by_secure_vip(&self, svip: &str) -> Vec<&Instance> {
+        self.find_instances(|instance| instance.serves_secure_vip(svip))
+    }
+
+    fn find_instances<F: Fn(&Instance) -> bool>(&self, f: F) -> Vec<&Instance> {
+        self.applications.applications.iter()
+            .flat_map(|app| app.instances.iter())
+            .filter(|instance| f(instance))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -43,6 +77,43 @@ mod tests {
         assert!(ar.is_ok())
     }
 
+    #[test]
+    fn test_applications_in_group() {
+        let json = local_eureka_json()
+            .replace("\"app\": \"INTEGRATION_TEST\",", "\"app\": \"INTEGRATION_TEST\", \"appGroupName\": \"Batch\",");
+        let ar: ApplicationsResponse = serde_json::from_str(&json).unwrap();
+
+        let in_group = ar.applications_in_group("BATCH");
+        assert_eq!(1, in_group.len());
+        assert_eq!("INTEGRATION_TEST", in_group[0].name);
+        assert!(ar.applications_in_group("OTHER").is_empty());
+    }
+
+    #[test]
+    fn test_applications_in_group_without_groups() {
+        let ar = ApplicationsResponse::new(build_test_applications());
+        assert!(ar.applications_in_group("BATCH").is_empty());
+    }
+
+    #[test]
+    fn test_instances_by_vip_match_any_vip() {
+        let json = local_eureka_json()
+            .replace("\"vipAddress\": \"127.0.0.1\"", "\"vipAddress\": \" orders, ,Orders-Canary \"")
+            .replace("\"secureVipAddress\": \"127.0.0.1\"", "\"secureVipAddress\": \"orders-secure,orders-canary-secure\"");
+        let ar: ApplicationsResponse = serde_json::from_str(&json).unwrap();
+
+        let instance = &ar.applications.applications[0].instances[0];
+        assert_eq!(vec!["orders", "Orders-Canary"], instance.vip_addresses());
+        assert_eq!(vec!["orders-secure", "orders-canary-secure"], instance.secure_vip_addresses());
+        for vip in &["orders", "orders-canary", "ORDERS-CANARY"] {
+            assert_eq!(1, ar.instances_by_vip(vip).len(), "{}", vip);
+        }
+        assert!(ar.instances_by_vip("orders-secure").is_empty());
+        assert_eq!(1, ar.instances_by_secure_vip("orders-canary-secure").len());
+        // the vip address is sent back as the server sent it
+        assert!(serde_json::to_string(&ar).unwrap().contains("\"vipAddress\":\" orders, ,Orders-Canary \""));
+    }
+
     fn build_applications_response_json() -> String {
         format!("{{\"applications\":{}}}", build_test_applications_json())
     }
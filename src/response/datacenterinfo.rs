@@ -1,5 +1,5 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use std::fmt;
 use super::DcName;
 use super::AmazonMetaData;
@@ -13,7 +13,7 @@ const CLASS_VALUE: &'static str = "com.netflix.appinfo.InstanceInfo$DefaultDataC
 const DATA_CENTER_INFO: &'static str = "DataCenterInfo";
 const FIELDS: &'static [&'static str] = &[CLASS, NAME, METADATA];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct DataCenterInfo {
     pub name: DcName,
     pub metadata: Option<AmazonMetaData>
@@ -34,7 +34,7 @@ impl Serialize for DataCenterInfo {
 impl<'de> Deserialize<'de> for DataCenterInfo {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { Name, Metadata, Class };
+        enum Field { Name, Metadata, Class, Ignore };
 
         impl<'de> Deserialize<'de> for Field {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
@@ -54,7 +54,7 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                             NAME => Ok(Field::Name),
                             METADATA => Ok(Field::Metadata),
                             CLASS => Ok(Field::Class),
-                            _ => Err(DeError::unknown_field(v, FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -74,7 +74,7 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                 A: MapAccess<'de> {
                 let mut maybe_name = None;
                 let mut maybe_metadata = None;
-                let mut maybe_class: Option<&str> = None;
+                let mut maybe_class: Option<String> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -88,11 +88,15 @@ impl<'de> Deserialize<'de> for DataCenterInfo {
                             if maybe_metadata.is_some() {
                                 return Err(DeError::duplicate_field(METADATA));
                             }
-                            maybe_metadata = Some(map.next_value()?);
+                            // null is what a missing metadata serializes to
+                            maybe_metadata = map.next_value::<Option<AmazonMetaData>>()?;
                         },
                         Field::Class => {
                             maybe_class = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
                 let name = maybe_name.ok_or_else(|| DeError::missing_field(NAME));
@@ -160,6 +164,16 @@ pub mod test {
         assert_eq!(dci, result);
     }
 
+    #[test]
+    fn test_data_center_info_without_metadata_round_trips() {
+        let dci = DataCenterInfo {
+            name: DcName::MyOwn,
+            metadata: None
+        };
+        let json = serde_json::to_string(&dci).unwrap();
+        assert_eq!(dci, serde_json::from_str(&json).unwrap());
+    }
+
     fn sample_data_center() -> String {
         format!("{{\"@class\":\"com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo\",\"name\":\"Amazon\",\"metadata\":{}}}", sample_meta_data())
     }
@@ -9,7 +9,7 @@ use std::error::Error;
 const MY_OWN: &'static str = "MyOwn";
 const AMAZON: &'static str = "Amazon";
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DcName {
     MyOwn,
     Amazon
@@ -0,0 +1,269 @@
+use std::mem;
+use serde_json::{self, Map, Value, Error as ParserError};
+use super::{ApplicationResponse, ApplicationsResponse, InstanceResponse};
+
+const APPLICATIONS: &'static str = "applications";
+const APPLICATION: &'static str = "application";
+const INSTANCE: &'static str = "instance";
+const NAME: &'static str = "name";
+const INSTANCE_INFO: &'static str = "instanceInfo";
+const DATA_CENTER_INFO: &'static str = "dataCenterInfo";
+const DATA_CENTER_METADATA: &'static str = "dataCenterMetadata";
+
+/// The flavour of json a eureka server speaks
+///
+/// Every dialect rewrites its instance payloads into the Netflix shape the response model is
+/// deserialized from, so supporting another eureka derivative only takes a new dialect.
+pub trait PayloadDialect {
+    /// A short name for the dialect, shown in the [EffectiveConfig](../struct.EffectiveConfig.html)
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+
+    /// Rewrites a single instance payload into the Netflix shape
+    fn normalize_instance(&self, instance: Value) -> Value;
+
+    /// Whether payloads need rewriting at all, dialects that return `false` are deserialized directly
+    fn rewrites_payload(&self) -> bool {
+        true
+    }
+}
+
+/// The json spoken by Netflix eureka servers, the default
+#[derive(Debug, Clone, Default)]
+pub struct NetflixDialect;
+
+impl PayloadDialect for NetflixDialect {
+    fn name(&self) -> &'static str {
+        "netflix"
+    }
+
+    fn normalize_instance(&self, instance: Value) -> Value {
+        instance
+    }
+
+    fn rewrites_payload(&self) -> bool {
+        false
+    }
+}
+
+/// Instances nested under an `instanceInfo` key, with `dataCenterMetadata` in place of `dataCenterInfo`
+#[derive(Debug, Clone, Default)]
+pub struct InstanceInfoDialect;
+
+impl PayloadDialect for InstanceInfoDialect {
+    fn name(&self) -> &'static str {
+        "instance-info"
+    }
+
+    fn normalize_instance(&self, instance: Value) -> Value {
+        let mut fields = match instance {
+            Value::Object(mut wrapper) => match wrapper.remove(INSTANCE_INFO) {
+                Some(Value::Object(fields)) => fields,
+                Some(other) => return other,
+                None => wrapper
+            },
+            other => return other
+        };
+        rename(&mut fields, DATA_CENTER_METADATA, DATA_CENTER_INFO);
+        Value::Object(fields)
+    }
+}
+
+fn rename(fields: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(value) = fields.remove(from) {
+        fields.insert(to.to_owned(), value);
+    }
+}
+
+pub(crate) fn parse_application(dialect: &PayloadDialect, body: &[u8]) -> Result<ApplicationResponse, ParserError> {
+    if !dialect.rewrites_payload() {
+        return serde_json::from_slice(body);
+    }
+    let mut payload: Value = serde_json::from_slice(body)?;
+    if let Some(application) = payload.get_mut(APPLICATION) {
+        normalize_application(dialect, application);
+    }
+    serde_json::from_value(payload)
+}
+
+// Some servers send the instance without the `instance` envelope
+pub(crate) fn parse_instance(dialect: &PayloadDialect, body: &[u8]) -> Result<InstanceResponse, ParserError> {
+    let payload: Value = serde_json::from_slice(body)?;
+    let instance = match payload {
+        Value::Object(mut envelope) => match envelope.remove(INSTANCE) {
+            Some(instance) => instance,
+            None => Value::Object(envelope)
+        },
+        other => other
+    };
+    let instance = if dialect.rewrites_payload() { dialect.normalize_instance(instance) } else { instance };
+    serde_json::from_value(instance).map(InstanceResponse::new)
+}
+
+pub(crate) fn parse_applications(dialect: &PayloadDialect, body: &[u8]) -> Result<ApplicationsResponse, ParserError> {
+    if !dialect.rewrites_payload() {
+        return serde_json::from_slice(body);
+    }
+    let mut payload: Value = serde_json::from_slice(body)?;
+    if let Some(applications) = payload.get_mut(APPLICATIONS).and_then(|a| a.get_mut(APPLICATION)) {
+        for_each(applications, |application| normalize_application(dialect, application));
+    }
+    serde_json::from_value(payload)
+}
+
+// Some servers nest the applications of every remote region in a block of their own next to
+// the local ones, such as `"us-east-1": {"application": [...]}`. They are merged into the
+// local list, instances of an application known to several regions end up under one name.
+pub(crate) fn parse_regional_applications(dialect: &PayloadDialect, body: &[u8]) -> Result<ApplicationsResponse, ParserError> {
+    let mut payload: Value = serde_json::from_slice(body)?;
+    if let Some(&mut Value::Object(ref mut applications)) = payload.get_mut(APPLICATIONS) {
+        merge_regions(applications);
+    }
+    let body = serde_json::to_vec(&payload)?;
+    parse_applications(dialect, &body)
+}
+
+fn merge_regions(applications: &mut Map<String, Value>) {
+    let regions: Vec<String> = applications.iter()
+        .filter(|&(name, value)| name != APPLICATION && value.get(APPLICATION).is_some())
+        .map(|(name, _)| name.clone())
+        .collect();
+    if regions.is_empty() {
+        return;
+    }
+    let mut merged = applications.remove(APPLICATION).map(into_vec).unwrap_or_default();
+    for region in regions {
+        let remote = match applications.remove(&region) {
+            Some(Value::Object(mut block)) => block.remove(APPLICATION).map(into_vec).unwrap_or_default(),
+            _ => continue
+        };
+        for application in remote {
+            let name = application.get(NAME).cloned();
+            match merged.iter_mut().find(|local| name.is_some() && local.get(NAME) == name.as_ref()) {
+                Some(local) => {
+                    let mut instances = local.get_mut(INSTANCE).map(|i| into_vec(mem::replace(i, Value::Null))).unwrap_or_default();
+                    instances.extend(application.get(INSTANCE).cloned().map(into_vec).unwrap_or_default());
+                    local[INSTANCE] = Value::Array(instances);
+                }
+                None => merged.push(application)
+            }
+        }
+    }
+    applications.insert(APPLICATION.to_owned(), Value::Array(merged));
+}
+
+fn into_vec(value: Value) -> Vec<Value> {
+    match value {
+        Value::Array(items) => items,
+        Value::Null => Vec::new(),
+        item => vec![item]
+    }
+}
+
+fn normalize_application(dialect: &PayloadDialect, application: &mut Value) {
+    if let Some(instances) = application.get_mut(INSTANCE) {
+        for_each(instances, |instance| {
+            let original = mem::replace(instance, Value::Null);
+            *instance = dialect.normalize_instance(original);
+        });
+    }
+}
+
+// eureka sends a bare object instead of an array when there is only one element
+fn for_each<F: FnMut(&mut Value)>(value: &mut Value, mut f: F) {
+    match *value {
+        Value::Array(ref mut items) => {
+            for item in items.iter_mut() {
+                f(item);
+            }
+        }
+        ref mut item => f(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Instance;
+    use super::super::instance::tests::{build_test_instance, build_test_instance_json};
+
+    // The test instance in the instanceInfo dialect
+    fn build_instance_info_json() -> String {
+        let json = build_test_instance_json().replace("\"dataCenterInfo\"", "\"dataCenterMetadata\"");
+        format!("{{\"instanceInfo\":{}}}", json)
+    }
+
+    #[test]
+    fn test_netflix_dialect_is_unchanged() {
+        let value: Value = serde_json::from_str(&build_test_instance_json()).unwrap();
+        assert_eq!(value.clone(), NetflixDialect.normalize_instance(value));
+    }
+
+    #[test]
+    fn test_dialects_parse_identically() {
+        let netflix = format!("{{\"application\":{{\"name\":\"Bar\",\"instance\":{}}}}}", build_test_instance_json());
+        let instance_info = format!("{{\"application\":{{\"name\":\"Bar\",\"instance\":[{}]}}}}", build_instance_info_json());
+
+        let netflix = parse_application(&NetflixDialect, netflix.as_bytes()).unwrap();
+        let instance_info = parse_application(&InstanceInfoDialect, instance_info.as_bytes()).unwrap();
+        assert_eq!(netflix, instance_info);
+        assert_eq!(vec![build_test_instance()], instance_info.application.instances);
+    }
+
+    #[test]
+    fn test_applications_in_instance_info_dialect() {
+        let json = format!("{{\"applications\":{{\"versions__delta\":1,\"apps__hashcode\":\"UP_2_\",\"application\":[\
+                            {{\"name\":\"Bar\",\"instance\":{}}},{{\"name\":\"Baz\",\"instance\":[{},{}]}}]}}}}",
+                           build_instance_info_json(), build_instance_info_json(), build_instance_info_json());
+        let response = parse_applications(&InstanceInfoDialect, json.as_bytes()).unwrap();
+        let instances: Vec<&Instance> = response.applications.applications.iter()
+            .flat_map(|a| a.instances.iter())
+            .collect();
+        assert_eq!(3, instances.len());
+        assert!(instances.iter().all(|i| **i == build_test_instance()));
+    }
+
+    #[test]
+    fn test_regional_blocks_are_merged() {
+        let instance = build_test_instance_json();
+        let json = format!("{{\"applications\":{{\"versions__delta\":1,\"apps__hashcode\":\"UP_4_\",\
+                            \"application\":{{\"name\":\"Bar\",\"instance\":{0}}},\
+                            \"eu-west-1\":{{\"application\":[{{\"name\":\"Bar\",\"instance\":{0}}},{{\"name\":\"Baz\",\"instance\":[{0},{0}]}}]}}}}}}",
+                           instance);
+        let response = parse_regional_applications(&NetflixDialect, json.as_bytes()).unwrap();
+        let counts: Vec<(&str, usize)> = response.applications.applications.iter()
+            .map(|a| (a.name.as_str(), a.instances.len()))
+            .collect();
+        assert_eq!(vec![("Bar", 2), ("Baz", 2)], counts);
+    }
+
+    #[test]
+    fn test_regional_parse_without_blocks() {
+        let json = format!("{{\"applications\":{{\"versions__delta\":1,\"apps__hashcode\":\"UP_1_\",\"application\":{{\"name\":\"Bar\",\"instance\":{}}}}}}}",
+                           build_test_instance_json());
+        assert_eq!(parse_applications(&NetflixDialect, json.as_bytes()).unwrap(),
+                   parse_regional_applications(&NetflixDialect, json.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_single_instance_in_instance_info_dialect() {
+        let json = format!("{{\"instance\":{}}}", build_instance_info_json());
+        let response = parse_instance(&InstanceInfoDialect, json.as_bytes()).unwrap();
+        assert_eq!(build_test_instance(), response.instance);
+    }
+
+    #[test]
+    fn test_instance_without_envelope() {
+        let netflix = parse_instance(&NetflixDialect, build_test_instance_json().as_bytes()).unwrap();
+        let instance_info = parse_instance(&InstanceInfoDialect, build_instance_info_json().as_bytes()).unwrap();
+        assert_eq!(build_test_instance(), netflix.instance);
+        assert_eq!(build_test_instance(), instance_info.instance);
+    }
+
+    #[test]
+    fn test_unwrapped_instances_pass_through() {
+        let json = format!("{{\"application\":{{\"name\":\"Bar\",\"instance\":{}}}}}", build_test_instance_json());
+        assert!(parse_application(&InstanceInfoDialect, json.as_bytes()).is_ok());
+    }
+}
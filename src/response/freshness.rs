@@ -0,0 +1,224 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use super::Instance;
+
+/// How trustworthy a discovered instance is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FreshnessLevel {
+    Fresh,
+    Aging,
+    Stale
+}
+
+/// The freshness of an instance with the ages it was judged on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Freshness {
+    pub level: FreshnessLevel,
+    /// Time since the lease was last renewed, `None` without lease info
+    pub renewal_age: Option<Duration>,
+    /// Time since the snapshot holding the instance was fetched
+    pub snapshot_age: Duration
+}
+
+/// Where a discovered instance came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotMeta {
+    /// When the response was fetched from eureka
+    pub fetched_at: SystemTime,
+    /// Whether the server that answered was in self preservation, so expired leases were
+    /// not being evicted
+    pub self_preservation: bool
+}
+
+impl SnapshotMeta {
+    /// A snapshot fetched just now from a server that was not in self preservation
+    pub fn now() -> SnapshotMeta {
+        SnapshotMeta {
+            fetched_at: SystemTime::now(),
+            self_preservation: false
+        }
+    }
+}
+
+/// The ages at which instances stop being fresh
+///
+/// An instance is as stale as the older of its lease renewal and its snapshot. While the
+/// server is in self preservation an aging renewal counts as stale, eureka is not evicting
+/// the instances that stopped renewing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreshnessThresholds {
+    pub renewal_aging: Duration,
+    pub renewal_stale: Duration,
+    pub snapshot_aging: Duration,
+    pub snapshot_stale: Duration
+}
+
+impl Default for FreshnessThresholds {
+    /// Two missed renewals at the default 30 second interval make an instance aging and an
+    /// expired 90 second lease makes it stale
+    fn default() -> Self {
+        FreshnessThresholds {
+            renewal_aging: Duration::from_secs(60),
+            renewal_stale: Duration::from_secs(90),
+            snapshot_aging: Duration::from_secs(60),
+            snapshot_stale: Duration::from_secs(300)
+        }
+    }
+}
+
+impl FreshnessThresholds {
+    /// Classifies an instance as of `now`
+    pub fn classify(&self, instance: &Instance, meta: &SnapshotMeta, now: SystemTime) -> Freshness {
+        let renewal_age = match instance.lease_info {
+            Some(ref lease) if lease.last_renewal_timestamp > 0 =>
+                Some(age(UNIX_EPOCH + Duration::from_millis(lease.last_renewal_timestamp as u64), now)),
+            _ => None
+        };
+        let snapshot_age = age(meta.fetched_at, now);
+
+        let snapshot_level = level(snapshot_age, self.snapshot_aging, self.snapshot_stale);
+        let renewal_level = match renewal_age.map(|age| level(age, self.renewal_aging, self.renewal_stale)) {
+            Some(FreshnessLevel::Aging) if meta.self_preservation => FreshnessLevel::Stale,
+            Some(level) => level,
+            None => FreshnessLevel::Fresh
+        };
+        Freshness {
+            level: if renewal_level > snapshot_level { renewal_level } else { snapshot_level },
+            renewal_age: renewal_age,
+            snapshot_age: snapshot_age
+        }
+    }
+
+    /// Orders instances fresh first and stale last, keeping the given order within a level
+    ///
+    /// Stale instances are kept, so there is still somewhere to go when every instance is stale.
+    pub fn prefer_fresh<'b>(&self, instances: &'b [Instance], meta: &SnapshotMeta, now: SystemTime) -> Vec<&'b Instance> {
+        let mut ranked: Vec<_> = instances.iter()
+            .map(|instance| (self.classify(instance, meta, now).level, instance))
+            .collect();
+        // sort_by_key is stable
+        ranked.sort_by_key(|&(level, _)| level);
+        ranked.into_iter().map(|(_, instance)| instance).collect()
+    }
+}
+
+impl Instance {
+    /// How fresh this instance is right now, with the default thresholds
+    pub fn freshness(&self, snapshot_meta: &SnapshotMeta) -> Freshness {
+        FreshnessThresholds::default().classify(self, snapshot_meta, SystemTime::now())
+    }
+}
+
+// clock skew can put timestamps in the future, those count as brand new
+fn age(since: SystemTime, now: SystemTime) -> Duration {
+    now.duration_since(since).unwrap_or_else(|_| Duration::from_secs(0))
+}
+
+fn level(age: Duration, aging: Duration, stale: Duration) -> FreshnessLevel {
+    if age >= stale {
+        FreshnessLevel::Stale
+    } else if age >= aging {
+        FreshnessLevel::Aging
+    } else {
+        FreshnessLevel::Fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::instance::tests::build_test_instance;
+
+    // the renewal timestamp of the test instance
+    fn renewed_at() -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(1503442035871)
+    }
+
+    fn classify(renewal_age_secs: u64, snapshot_age_secs: u64, self_preservation: bool) -> FreshnessLevel {
+        let now = renewed_at() + Duration::from_secs(renewal_age_secs);
+        let meta = SnapshotMeta {
+            fetched_at: now - Duration::from_secs(snapshot_age_secs),
+            self_preservation: self_preservation
+        };
+        FreshnessThresholds::default().classify(&build_test_instance(), &meta, now).level
+    }
+
+    #[test]
+    fn test_classification_matrix() {
+        use self::FreshnessLevel::*;
+        let cases = vec![
+            // renewal age, snapshot age, self preservation, expected
+            (0, 0, false, Fresh),
+            (59, 59, false, Fresh),
+            (60, 0, false, Aging),
+            (0, 60, false, Aging),
+            (90, 0, false, Stale),
+            (0, 300, false, Stale),
+            (60, 299, false, Aging),
+            (0, 0, true, Fresh),
+            (60, 0, true, Stale),
+            (0, 60, true, Aging),
+            (600, 600, true, Stale)
+        ];
+        for (renewal, snapshot, self_preservation, expected) in cases {
+            assert_eq!(expected, classify(renewal, snapshot, self_preservation),
+                       "renewal {}s, snapshot {}s, self preservation {}", renewal, snapshot, self_preservation);
+        }
+    }
+
+    #[test]
+    fn test_ages_are_reported() {
+        let now = renewed_at() + Duration::from_secs(70);
+        let meta = SnapshotMeta { fetched_at: now - Duration::from_secs(5), self_preservation: false };
+        let freshness = FreshnessThresholds::default().classify(&build_test_instance(), &meta, now);
+        assert_eq!(Some(Duration::from_secs(70)), freshness.renewal_age);
+        assert_eq!(Duration::from_secs(5), freshness.snapshot_age);
+    }
+
+    #[test]
+    fn test_without_lease_info_only_the_snapshot_counts() {
+        let mut instance = build_test_instance();
+        instance.lease_info = None;
+        let now = SystemTime::now();
+        let meta = SnapshotMeta { fetched_at: now, self_preservation: true };
+        let freshness = FreshnessThresholds::default().classify(&instance, &meta, now);
+        assert_eq!(FreshnessLevel::Fresh, freshness.level);
+        assert_eq!(None, freshness.renewal_age);
+    }
+
+    #[test]
+    fn test_future_timestamps_are_fresh() {
+        let now = renewed_at() - Duration::from_secs(10);
+        let meta = SnapshotMeta { fetched_at: now + Duration::from_secs(10), self_preservation: false };
+        assert_eq!(FreshnessLevel::Fresh, FreshnessThresholds::default().classify(&build_test_instance(), &meta, now).level);
+    }
+
+    #[test]
+    fn test_prefer_fresh_keeps_stale_instances_last() {
+        let mut stale = build_test_instance();
+        stale.host_name = "stale".to_owned();
+        stale.lease_info.as_mut().unwrap().last_renewal_timestamp -= 120 * 1000;
+        let mut first = build_test_instance();
+        first.host_name = "first".to_owned();
+        let mut second = build_test_instance();
+        second.host_name = "second".to_owned();
+        let instances = vec![stale, first, second];
+
+        let now = renewed_at();
+        let meta = SnapshotMeta { fetched_at: now, self_preservation: false };
+        let ordered: Vec<_> = FreshnessThresholds::default().prefer_fresh(&instances, &meta, now)
+            .into_iter().map(|i| i.host_name.as_str()).collect();
+        assert_eq!(vec!["first", "second", "stale"], ordered);
+    }
+
+    #[test]
+    fn test_custom_thresholds() {
+        let thresholds = FreshnessThresholds {
+            renewal_aging: Duration::from_secs(5),
+            renewal_stale: Duration::from_secs(10),
+            ..FreshnessThresholds::default()
+        };
+        let now = renewed_at() + Duration::from_secs(10);
+        let meta = SnapshotMeta { fetched_at: now, self_preservation: false };
+        assert_eq!(FreshnessLevel::Stale, thresholds.classify(&build_test_instance(), &meta, now).level);
+    }
+}
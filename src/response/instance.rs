@@ -1,17 +1,23 @@
 use serde::ser::{Serialize, Serializer, SerializeStruct};
-use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, MapAccess};
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError, IgnoredAny, MapAccess};
 use serde_json::{Map, Value};
 use std::fmt;
-use std::str::FromStr;
 use super::DataCenterInfo;
+use super::DcName;
 use super::LeaseInfo;
 use super::Status;
 use super::ActionType;
+use super::lenient::{Integer, Flag, PortNumber};
+use keys::{METADATA_MANAGEMENT_PORT, METADATA_VERSION, METADATA_ZONE};
+use metadata::MetadataExt;
+use vip;
 
 // Field name constants
 const INSTANCE: &'static str = "Instance";
+const INSTANCE_ID: &'static str = "instanceId";
 const HOST_NAME: &'static str = "hostName";
 const APP: &'static str = "app";
+const APP_GROUP_NAME: &'static str = "appGroupName";
 const IP_ADDR: &'static str = "ipAddr";
 const VIP_ADDRESS: &'static str = "vipAddress";
 const SECURE_VIP_ADDRESS: &'static str = "secureVipAddress";
@@ -30,11 +36,7 @@ const LAST_UPDATED_TIMESTAMP: &'static str = "lastUpdatedTimestamp";
 const LAST_DIRTY_TIMESTAMP: &'static str = "lastDirtyTimestamp";
 const ACTION_TYPE: &'static str = "actionType";
 const IS_COORDINATED_DISCOVERY_SERVER: &'static str = "isCoordinatingDiscoveryServer";
-const JSON_FIELDS: &'static [&'static str] = &[INSTANCE, HOST_NAME, APP, IP_ADDR, VIP_ADDRESS, SECURE_VIP_ADDRESS,
-    STATUS, PORT, SECURE_PORT, HOME_PAGE_URL, STATUS_PAGE_URL, HEALTH_CHECK_URL,
-    DATA_CENTER_INFO, LEASE_INFO, METADATA, OVERRIDDENSTATUS, COUNTRY_ID, LAST_UPDATED_TIMESTAMP, LAST_DIRTY_TIMESTAMP,
-    ACTION_TYPE, IS_COORDINATED_DISCOVERY_SERVER];
-const RUST_FIELDS: &'static [&'static str] = &["host_name", "app", "ip_addr", "vip_address", "secure_vip_address",
+const RUST_FIELDS: &'static [&'static str] = &["instance_id", "host_name", "app", "app_group_name", "ip_addr", "vip_address", "secure_vip_address",
     "status", "port Option", "secure_port", "homepage_url", "status_page_url",
     "health_check_url", "data_center_info", "lease_info", "metadata", OVERRIDDENSTATUS, "country_id", "last_updated_timestamp",
     "last_dirty_timestamp", "action_type", "is_coordinating_discovery_server"];
@@ -43,10 +45,12 @@ const PORT_DOLLAR: &'static str = "$";
 const PORT_ENABLED: &'static str = "@enabled";
 const PORT_FIELDS: &'static [&'static str] = &[PORT_DOLLAR, PORT_ENABLED];
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Instance {
+    pub instance_id: Option<String>,
     pub host_name: String,
     pub app: String,
+    pub app_group_name: Option<String>,
     pub ip_addr: String,
     pub vip_address: String,
     pub secure_vip_address: String,
@@ -67,6 +71,73 @@ pub struct Instance {
     pub is_coordinating_discovery_server: bool
 }
 
+impl Instance {
+    /// The id eureka knows this instance by
+    ///
+    /// This is the instanceId when the server sent one, otherwise the amazon instance id
+    /// for instances running in amazon, otherwise the host name. The same rules are used
+    /// by eureka itself.
+    pub fn id(&self) -> &str {
+        if let &Some(ref instance_id) = &self.instance_id {
+            return instance_id;
+        }
+        if let &Some(ref metadata) = &self.data_center_info.metadata {
+            if self.data_center_info.name == DcName::Amazon {
+                return &metadata.instance_id;
+            }
+        }
+        &self.host_name
+    }
+
+    /// The application group this instance belongs to, if any
+    pub fn app_group_name(&self) -> Option<&str> {
+        self.app_group_name.as_ref().map(|g| g.as_ref())
+    }
+
+    /// The vips this instance serves, from its comma separated vip address
+    ///
+    /// The addresses are trimmed and empty ones are left out, `vip_address` itself is kept
+    /// as the server sent it.
+    pub fn vip_addresses(&self) -> Vec<&str> {
+        vip::split(&self.vip_address)
+    }
+
+    /// The secure vips this instance serves, see [vip_addresses](#method.vip_addresses)
+    pub fn secure_vip_addresses(&self) -> Vec<&str> {
+        vip::split(&self.secure_vip_address)
+    }
+
+    /// Whether this instance serves `vip`, matching any of its vip addresses in any case
+    pub fn serves_vip(&self, vip: &str) -> bool {
+        vip::contains(&self.vip_address, vip)
+    }
+
+    /// Whether this instance serves the secure vip `svip`, see [serves_vip](#method.serves_vip)
+    pub fn serves_secure_vip(&self, svip: &str) -> bool {
+        vip::contains(&self.secure_vip_address, svip)
+    }
+
+    /// The zone from the metadata, matching the key in any case
+    pub fn zone(&self) -> Option<&str> {
+        self.metadata.get_ci(METADATA_ZONE).and_then(|v| v.as_str())
+    }
+
+    /// The version from the metadata, matching the key in any case
+    pub fn version(&self) -> Option<&str> {
+        self.metadata.get_ci(METADATA_VERSION).and_then(|v| v.as_str())
+    }
+
+    /// The management port from the metadata, sent either as a string or a number
+    #[allow(clippy::legacy_numeric_constants)]
+    pub fn management_port(&self) -> Option<u16> {
+        match self.metadata.get_ci(METADATA_MANAGEMENT_PORT) {
+            Some(&Value::String(ref port)) => port.parse().ok(),
+            Some(&Value::Number(ref port)) => port.as_u64().and_then(|port| if port <= u64::from(u16::max_value()) { Some(port as u16) } else { None }),
+            _ => None
+        }
+    }
+}
+
 struct Port {
     port: u16
 }
@@ -90,7 +161,7 @@ impl Serialize for Port {
 impl<'de> Deserialize<'de> for Port {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
-        enum Field { DollarSign, Enabled };
+        enum Field { DollarSign, Enabled, Ignore };
 
 
         impl<'de> Deserialize<'de> for Field {
@@ -109,7 +180,7 @@ impl<'de> Deserialize<'de> for Port {
                         match v {
                             PORT_DOLLAR => Ok(Field::DollarSign),
                             PORT_ENABLED => Ok(Field::Enabled),
-                            _ => Err(DeError::unknown_field(v, PORT_FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -127,7 +198,7 @@ impl<'de> Deserialize<'de> for Port {
 
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where
                 A: MapAccess<'de>, {
-                let mut maybe_dollar: Option<String> = None;
+                let mut maybe_dollar: Option<PortNumber> = None;
                 let mut maybe_enabled: Option<String> = None;
 
                 while let Some(key) = map.next_key()? {
@@ -144,11 +215,13 @@ impl<'de> Deserialize<'de> for Port {
                             }
                             maybe_enabled = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
 
-                let dollar = maybe_dollar
-                    .map(|s| u16::from_str(s.as_ref()).unwrap())
+                let PortNumber(dollar) = maybe_dollar
                     .ok_or_else(|| DeError::missing_field(PORT_DOLLAR))?;
                 maybe_enabled.ok_or_else(|| DeError::missing_field(PORT_ENABLED))?;
                 // ignore enabled
@@ -164,8 +237,18 @@ impl Serialize for Instance {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where
         S: Serializer {
         let mut s = serializer.serialize_struct(INSTANCE, 14)?;
+
+        if let &Some(ref instance_id) = &self.instance_id {
+            s.serialize_field(INSTANCE_ID, instance_id)?;
+        }
+
         s.serialize_field(HOST_NAME, &self.host_name)?;
         s.serialize_field(APP, &self.app)?;
+
+        if let &Some(ref app_group_name) = &self.app_group_name {
+            s.serialize_field(APP_GROUP_NAME, app_group_name)?;
+        }
+
         s.serialize_field(IP_ADDR, &self.ip_addr)?;
         s.serialize_field(VIP_ADDRESS, &self.vip_address)?;
         s.serialize_field(SECURE_VIP_ADDRESS, &self.secure_vip_address)?;
@@ -213,8 +296,10 @@ impl<'de> Deserialize<'de> for Instance {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
         D: Deserializer<'de> {
         enum Field {
+            InstanceId,
             HostName,
             App,
+            AppGroupName,
             IpAddr,
             VipAddress,
             SecureVipAddress,
@@ -232,7 +317,8 @@ impl<'de> Deserialize<'de> for Instance {
             LastUpdatedTimestamp,
             LastDirtyTimestamp,
             IsCoordinatingDiscoveryServer,
-            ActionType
+            ActionType,
+            Ignore
         }
 
         impl<'de> Deserialize<'de> for Field {
@@ -249,8 +335,10 @@ impl<'de> Deserialize<'de> for Instance {
                     fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where
                         E: DeError {
                         match v {
+                            INSTANCE_ID => Ok(Field::InstanceId),
                             HOST_NAME => Ok(Field::HostName),
                             APP => Ok(Field::App),
+                            APP_GROUP_NAME => Ok(Field::AppGroupName),
                             IP_ADDR => Ok(Field::IpAddr),
                             VIP_ADDRESS => Ok(Field::VipAddress),
                             SECURE_VIP_ADDRESS => Ok(Field::SecureVipAddress),
@@ -269,7 +357,7 @@ impl<'de> Deserialize<'de> for Instance {
                             LAST_DIRTY_TIMESTAMP => Ok(Field::LastDirtyTimestamp),
                             IS_COORDINATED_DISCOVERY_SERVER => Ok(Field::IsCoordinatingDiscoveryServer),
                             ACTION_TYPE => Ok(Field::ActionType),
-                            _ => Err(DeError::unknown_field(v, JSON_FIELDS))
+                            _ => Ok(Field::Ignore)
                         }
                     }
                 }
@@ -289,8 +377,10 @@ impl<'de> Deserialize<'de> for Instance {
 
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error> where
                 A: MapAccess<'de> {
+                let mut maybe_instance_id = None;
                 let mut maybe_host_name = None;
                 let mut maybe_app = None;
+                let mut maybe_app_group_name = None;
                 let mut maybe_ip_addr = None;
                 let mut maybe_vip_address = None;
                 let mut maybe_secure_vip_address = None;
@@ -324,6 +414,12 @@ impl<'de> Deserialize<'de> for Instance {
                             }
                             maybe_app = Some(map.next_value()?);
                         },
+                        Field::AppGroupName => {
+                            if maybe_app_group_name.is_some() {
+                                return Err(DeError::duplicate_field(APP_GROUP_NAME));
+                            }
+                            maybe_app_group_name = Some(map.next_value()?);
+                        },
                         Field::IpAddr => {
                             if maybe_ip_addr.is_some() {
                                 return Err(DeError::duplicate_field(IP_ADDR));
@@ -390,6 +486,12 @@ impl<'de> Deserialize<'de> for Instance {
                             }
                             maybe_metadata = Some(map.next_value()?);
                         },
+                        Field::InstanceId => {
+                            if maybe_instance_id.is_some() {
+                                return Err(DeError::duplicate_field(INSTANCE_ID));
+                            }
+                            maybe_instance_id = Some(map.next_value()?);
+                        },
                         Field::HostName => {
                             if maybe_host_name.is_some() {
                                 return Err(DeError::duplicate_field(HOST_NAME));
@@ -412,19 +514,19 @@ impl<'de> Deserialize<'de> for Instance {
                             if maybe_last_updated_timestamp.is_some() {
                                 return Err(DeError::duplicate_field(LAST_UPDATED_TIMESTAMP));
                             }
-                            maybe_last_updated_timestamp = Some(map.next_value()?);
+                            maybe_last_updated_timestamp = Some(map.next_value::<Integer>()?.0);
                         },
                         Field::LastDirtyTimestamp => {
                             if maybe_last_dirty_timestamp.is_some() {
                                 return Err(DeError::duplicate_field(LAST_DIRTY_TIMESTAMP));
                             }
-                            maybe_last_dirty_timestamp = Some(map.next_value()?);
+                            maybe_last_dirty_timestamp = Some(map.next_value::<Integer>()?.0);
                         },
                         Field::IsCoordinatingDiscoveryServer => {
                             if maybe_is_coordinating_discovery_server.is_some() {
                                 return Err(DeError::duplicate_field(IS_COORDINATED_DISCOVERY_SERVER));
                             }
-                            maybe_is_coordinating_discovery_server = Some(map.next_value()?);
+                            maybe_is_coordinating_discovery_server = Some(map.next_value::<Flag>()?.0);
                         },
                         Field::ActionType => {
                             if maybe_action_type.is_some() {
@@ -432,6 +534,9 @@ impl<'de> Deserialize<'de> for Instance {
                             }
                             maybe_action_type = Some(map.next_value()?);
                         }
+                        Field::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
                     }
                 }
 
@@ -458,8 +563,10 @@ impl<'de> Deserialize<'de> for Instance {
                 let country_id = maybe_country_id.ok_or_else(|| DeError::missing_field(COUNTRY_ID));
 
                 Ok(Instance {
+                    instance_id: maybe_instance_id,
                     host_name: host_name?,
                     app: app?,
+                    app_group_name: maybe_app_group_name,
                     ip_addr: ip_addr?,
                     vip_address: vip_address?,
                     secure_vip_address: secure_vip_address?,
@@ -489,9 +596,25 @@ impl<'de> Deserialize<'de> for Instance {
 pub mod tests {
     use super::*;
     use serde_json;
-    use super::super::DcName;
     use super::super::AmazonMetaData;
 
+    #[test]
+    fn test_well_known_metadata_ignores_case() {
+        let mut instance = build_test_instance();
+        instance.metadata.insert("Zone".to_owned(), Value::String("primary".to_owned()));
+        instance.metadata.insert("VERSION".to_owned(), Value::String("1.2.3".to_owned()));
+        instance.metadata.insert("Management.Port".to_owned(), Value::String("8081".to_owned()));
+        assert_eq!(Some("primary"), instance.zone());
+        assert_eq!(Some("1.2.3"), instance.version());
+        assert_eq!(Some(8081), instance.management_port());
+
+        instance.metadata.insert("Management.Port".to_owned(), Value::from(9091));
+        assert_eq!(Some(9091), instance.management_port());
+        instance.metadata.insert("Management.Port".to_owned(), Value::from(70000));
+        assert_eq!(None, instance.management_port());
+        assert_eq!(None, build_test_instance().zone());
+    }
+
     #[test]
     fn test_instance_serialization() {
         let json = build_test_instance_json();
@@ -566,8 +689,10 @@ pub mod tests {
         let mut metadata = Map::new();
         metadata.insert("something".to_owned(), Value::String("somethingelse".to_owned()));
         Instance {
+            instance_id: None,
             host_name: "Foo".to_string(),
             app: "Bar".to_string(),
+            app_group_name: None,
             ip_addr: "3.128.2.12".to_string(),
             vip_address: "127.0.0.1".to_string(),
             secure_vip_address: "127.0.0.2".to_string(),
@@ -611,6 +736,58 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_app_group_name_round_trip() {
+        let json = build_test_instance_json()
+            .replace("\"app\":\"Bar\",", "\"app\":\"Bar\",\"appGroupName\":\"BATCH\",");
+        let instance: Instance = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some("BATCH"), instance.app_group_name());
+        assert_eq!(json, serde_json::to_string(&instance).unwrap());
+    }
+
+    #[test]
+    fn test_instance_id_round_trip() {
+        let json = build_test_instance_json().replacen("{", "{\"instanceId\":\"foo:bar:80\",", 1);
+        let instance: Instance = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some("foo:bar:80".to_owned()), instance.instance_id);
+        assert_eq!(json, serde_json::to_string(&instance).unwrap());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_skipped() {
+        let json = build_test_instance_json()
+            .replacen("{", "{\"overriddenStatus\":\"UNKNOWN\",\"sid\":\"na\",\"extra\":{\"nested\":[1,{\"a\":null}]},", 1)
+            .replace("\"@enabled\":\"true\"", "\"@enabled\":\"true\",\"@secure\":\"false\"");
+        let instance: Instance = serde_json::from_str(&json).unwrap();
+        assert_eq!(build_test_instance(), instance);
+    }
+
+    #[test]
+    fn test_captured_instance_round_trip() {
+        // GET /eureka/v2/apps/ORDERS/{instanceId} as Netflix eureka answers it
+        let captured: Value = serde_json::from_str(include_str!("../../tests/fixtures/instance.json")).unwrap();
+        let instance: Instance = serde_json::from_value(captured["instance"].clone()).unwrap();
+        assert_eq!("orders", instance.vip_address);
+        assert_eq!("orders-secure", instance.secure_vip_address);
+
+        let serialized: Value = serde_json::from_str(&serde_json::to_string(&instance).unwrap()).unwrap();
+        assert_eq!("orders", serialized[VIP_ADDRESS]);
+        assert_eq!("orders-secure", serialized[SECURE_VIP_ADDRESS]);
+        assert_eq!(instance, serde_json::from_value(serialized).unwrap());
+    }
+
+    #[test]
+    fn test_id() {
+        let mut instance = build_test_instance();
+        assert_eq!("instance1a", instance.id());
+
+        instance.data_center_info.name = DcName::MyOwn;
+        assert_eq!("Foo", instance.id());
+
+        instance.instance_id = Some("foo:bar:80".to_owned());
+        assert_eq!("foo:bar:80", instance.id());
+    }
+
     #[test]
     fn test_empty_map() {
         let json = r#"{
@@ -0,0 +1,30 @@
+use super::Instance;
+
+/// A single instance, as returned by `GET /v2/apps/{appId}/{instanceId}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceResponse {
+    pub instance: Instance
+}
+
+impl InstanceResponse {
+    pub fn new(instance: Instance) -> InstanceResponse {
+        InstanceResponse {
+            instance: instance
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use super::*;
+    use super::super::instance::tests::{build_test_instance, build_test_instance_json};
+
+    #[test]
+    fn test_instance_response_serialization() {
+        let json = format!("{{\"instance\":{}}}", build_test_instance_json());
+        let response = InstanceResponse::new(build_test_instance());
+        assert_eq!(json, serde_json::to_string(&response).unwrap());
+        assert_eq!(response, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LeaseInfo {
     pub renewal_interval_in_secs: i64,
@@ -0,0 +1,137 @@
+// Eureka's own codec writes several numbers and flags of a registry as strings, as in
+// "lastDirtyTimestamp":"1503442035714" or "isCoordinatingDiscoveryServer":"false", and ports
+// as numbers. These accept a value either way.
+use std::fmt;
+use std::str::FromStr;
+use serde::de::{Deserialize, Deserializer, Visitor, Error as DeError};
+
+/// An integer, or a string holding one
+pub(crate) struct Integer(pub i64);
+
+impl<'de> Deserialize<'de> for Integer {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        struct IntegerVisitor;
+
+        impl<'de> Visitor<'de> for IntegerVisitor {
+            type Value = Integer;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an integer or a string holding one")
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where
+                E: DeError {
+                Ok(Integer(v))
+            }
+
+            #[allow(clippy::legacy_numeric_constants)]
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where
+                E: DeError {
+                if v > i64::max_value() as u64 {
+                    return Err(DeError::custom(format!("{} is out of range", v)));
+                }
+                Ok(Integer(v as i64))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where
+                E: DeError {
+                i64::from_str(v.trim())
+                    .map(Integer)
+                    .map_err(|_| DeError::custom(format!("invalid integer {:?}", v)))
+            }
+        }
+
+        deserializer.deserialize_any(IntegerVisitor)
+    }
+}
+
+/// A port number, or a string holding one
+pub(crate) struct PortNumber(pub u16);
+
+impl<'de> Deserialize<'de> for PortNumber {
+    #[allow(clippy::legacy_numeric_constants)]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        let Integer(port) = Integer::deserialize(deserializer)
+            .map_err(|e| DeError::custom(format!("invalid port: {}", e)))?;
+        if port < 0 || port > u16::max_value() as i64 {
+            return Err(DeError::custom(format!("port {} is out of range", port)));
+        }
+        Ok(PortNumber(port as u16))
+    }
+}
+
+/// A boolean, or the string "true" or "false"
+pub(crate) struct Flag(pub bool);
+
+impl<'de> Deserialize<'de> for Flag {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where
+        D: Deserializer<'de> {
+        struct FlagVisitor;
+
+        impl<'de> Visitor<'de> for FlagVisitor {
+            type Value = Flag;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a boolean or a string holding one")
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> where
+                E: DeError {
+                Ok(Flag(v))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where
+                E: DeError {
+                bool::from_str(v.trim())
+                    .map(Flag)
+                    .map_err(|_| DeError::custom(format!("invalid boolean {:?}", v)))
+            }
+        }
+
+        deserializer.deserialize_any(FlagVisitor)
+    }
+}
+
+/// For `versions__delta`, which eureka writes as a string
+#[allow(clippy::legacy_numeric_constants)]
+pub(crate) fn deserialize_versions_delta<'de, D>(deserializer: D) -> Result<i16, D::Error>
+    where D: Deserializer<'de> {
+    let Integer(delta) = Integer::deserialize(deserializer)?;
+    if delta < i16::min_value() as i64 || delta > i16::max_value() as i64 {
+        return Err(DeError::custom(format!("versions__delta {} is out of range", delta)));
+    }
+    Ok(delta as i16)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use super::*;
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(1503442035714, serde_json::from_str::<Integer>("1503442035714").unwrap().0);
+        assert_eq!(1503442035714, serde_json::from_str::<Integer>("\"1503442035714\"").unwrap().0);
+        assert_eq!(-1, serde_json::from_str::<Integer>("\"-1\"").unwrap().0);
+        assert!(serde_json::from_str::<Integer>("\"soon\"").is_err());
+        assert!(serde_json::from_str::<Integer>("18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn test_port_number() {
+        assert_eq!(8080, serde_json::from_str::<PortNumber>("8080").unwrap().0);
+        assert_eq!(8080, serde_json::from_str::<PortNumber>("\"8080\"").unwrap().0);
+        assert!(serde_json::from_str::<PortNumber>("\"http\"").is_err());
+        assert!(serde_json::from_str::<PortNumber>("\"70000\"").is_err());
+        assert!(serde_json::from_str::<PortNumber>("-1").is_err());
+    }
+
+    #[test]
+    fn test_flag() {
+        assert!(!serde_json::from_str::<Flag>("false").unwrap().0);
+        assert!(serde_json::from_str::<Flag>("\"true\"").unwrap().0);
+        assert!(serde_json::from_str::<Flag>("\"yes\"").is_err());
+    }
+}
@@ -3,12 +3,22 @@ mod dcname;
 mod amazonmetadata;
 mod datacenterinfo;
 mod leaseinfo;
-mod instance;
+pub(crate) mod instance;
 mod application;
 mod application_response;
+mod instance_response;
 mod applications_response;
+mod applications_delta_response;
 mod applications;
 mod action_type;
+mod server_health;
+mod dialect;
+mod pagination;
+mod snapshot;
+mod persisted;
+mod freshness;
+mod reconcile;
+mod lenient;
 
 pub use self::status::Status;
 pub use self::dcname::DcName;
@@ -20,4 +30,14 @@ pub use self::application::Application;
 pub use self::action_type::ActionType;
 pub use self::applications::Applications;
 pub use self::application_response::ApplicationResponse;
+pub use self::instance_response::InstanceResponse;
 pub use self::applications_response::ApplicationsResponse;
+pub use self::applications_delta_response::ApplicationsDeltaResponse;
+pub use self::server_health::ServerHealth;
+pub use self::dialect::{PayloadDialect, NetflixDialect, InstanceInfoDialect};
+pub use self::pagination::{SortKey, InstancePage, InstancePager};
+pub use self::snapshot::{SnapshotReader, SnapshotError};
+pub use self::persisted::{PersistedSnapshot, PersistedSnapshotError, SnapshotPolicy, SNAPSHOT_FORMAT_VERSION};
+pub use self::reconcile::{InstanceKey, Reconciliation, reconcile};
+pub use self::freshness::{Freshness, FreshnessLevel, FreshnessThresholds, SnapshotMeta};
+pub(crate) use self::dialect::{parse_application, parse_applications, parse_instance, parse_regional_applications};
@@ -0,0 +1,178 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::rc::Rc;
+use super::{ApplicationResponse, Instance, Status};
+
+/// The order instances are paged in, ties keep the order of the response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    InstanceId,
+    HostName,
+    /// Up, down, starting, out of service, then unknown
+    Status,
+    /// Oldest lastUpdatedTimestamp first
+    LastUpdated
+}
+
+const SORT_KEYS: usize = 4;
+
+impl SortKey {
+    fn index(&self) -> usize {
+        match *self {
+            SortKey::InstanceId => 0,
+            SortKey::HostName => 1,
+            SortKey::Status => 2,
+            SortKey::LastUpdated => 3
+        }
+    }
+
+    fn compare(&self, a: &Instance, b: &Instance) -> Ordering {
+        match *self {
+            SortKey::InstanceId => a.id().cmp(b.id()),
+            SortKey::HostName => a.host_name.cmp(&b.host_name),
+            SortKey::Status => status_rank(&a.status).cmp(&status_rank(&b.status)),
+            SortKey::LastUpdated => a.last_updated_timestamp.cmp(&b.last_updated_timestamp)
+        }
+    }
+}
+
+fn status_rank(status: &Status) -> u8 {
+    match *status {
+        Status::Up => 0,
+        Status::Down => 1,
+        Status::Starting => 2,
+        Status::OutOfService => 3,
+        Status::Unknown => 4
+    }
+}
+
+/// A page of instances
+#[derive(Debug, PartialEq)]
+pub struct InstancePage<'a> {
+    pub instances: Vec<&'a Instance>,
+    pub offset: usize,
+    /// The number of instances in the whole application
+    pub total: usize
+}
+
+/// Pages through the instances of one [ApplicationResponse](struct.ApplicationResponse.html)
+///
+/// Each sort order is computed once, on the first page asked for in that order, and reused for
+/// every later page. Keep the pager around while paging so large applications are not re-sorted
+/// for every page.
+pub struct InstancePager<'a> {
+    response: &'a ApplicationResponse,
+    orders: RefCell<[Option<Rc<Vec<usize>>>; SORT_KEYS]>
+}
+
+impl<'a> InstancePager<'a> {
+    pub fn new(response: &'a ApplicationResponse) -> InstancePager<'a> {
+        InstancePager {
+            response: response,
+            orders: RefCell::new([None, None, None, None])
+        }
+    }
+
+    /// The instances from `offset` on, at most `limit` of them
+    ///
+    /// An offset past the last instance gives an empty page.
+    pub fn instances_page(&self, offset: usize, limit: usize, sort: SortKey) -> InstancePage<'a> {
+        let instances = &self.response.application.instances;
+        let order = self.order(sort);
+        let page = order.iter()
+            .skip(offset)
+            .take(limit)
+            .map(|&i| &instances[i])
+            .collect();
+        InstancePage {
+            instances: page,
+            offset: offset,
+            total: instances.len()
+        }
+    }
+
+    fn order(&self, sort: SortKey) -> Rc<Vec<usize>> {
+        if let Some(ref order) = self.orders.borrow()[sort.index()] {
+            return order.clone();
+        }
+        let instances = &self.response.application.instances;
+        let mut order: Vec<usize> = (0..instances.len()).collect();
+        // sort_by is stable, so ties stay in response order
+        order.sort_by(|&a, &b| sort.compare(&instances[a], &instances[b]));
+        let order = Rc::new(order);
+        self.orders.borrow_mut()[sort.index()] = Some(order.clone());
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Application;
+    use super::super::instance::tests::build_test_instance;
+
+    fn instance(host_name: &str, status: Status, last_updated: i64) -> Instance {
+        let mut instance = build_test_instance();
+        instance.instance_id = Some(host_name.to_owned());
+        instance.host_name = host_name.to_owned();
+        instance.status = status;
+        instance.last_updated_timestamp = last_updated;
+        instance
+    }
+
+    fn response() -> ApplicationResponse {
+        ApplicationResponse::new(Application {
+            name: "PAGED".to_owned(),
+            instances: vec![
+                instance("c", Status::Down, 3),
+                instance("a", Status::Up, 2),
+                instance("d", Status::Up, 2),
+                instance("b", Status::Starting, 1)
+            ]
+        })
+    }
+
+    fn hosts(page: &InstancePage) -> Vec<String> {
+        page.instances.iter().map(|i| i.host_name.clone()).collect()
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let response = response();
+        let pager = response.pager();
+        assert_eq!(vec!["a", "b", "c", "d"], hosts(&pager.instances_page(0, 10, SortKey::InstanceId)));
+        assert_eq!(vec!["a", "b", "c", "d"], hosts(&pager.instances_page(0, 10, SortKey::HostName)));
+        // ties keep the response order
+        assert_eq!(vec!["a", "d", "c", "b"], hosts(&pager.instances_page(0, 10, SortKey::Status)));
+        assert_eq!(vec!["b", "a", "d", "c"], hosts(&pager.instances_page(0, 10, SortKey::LastUpdated)));
+    }
+
+    #[test]
+    fn test_pages_cover_every_instance_once() {
+        let response = response();
+        let pager = response.pager();
+        let first = pager.instances_page(0, 3, SortKey::HostName);
+        let second = pager.instances_page(3, 3, SortKey::HostName);
+        assert_eq!(4, first.total);
+        assert_eq!(4, second.total);
+        assert_eq!(vec!["a", "b", "c"], hosts(&first));
+        assert_eq!(vec!["d"], hosts(&second));
+        assert_eq!(first, pager.instances_page(0, 3, SortKey::HostName));
+    }
+
+    #[test]
+    fn test_out_of_range_offset() {
+        let response = response();
+        let page = response.pager().instances_page(10, 3, SortKey::InstanceId);
+        assert!(page.instances.is_empty());
+        assert_eq!(10, page.offset);
+        assert_eq!(4, page.total);
+    }
+
+    #[test]
+    fn test_order_is_sorted_once() {
+        let response = response();
+        let pager = response.pager();
+        assert!(Rc::ptr_eq(&pager.order(SortKey::Status), &pager.order(SortKey::Status)));
+    }
+}
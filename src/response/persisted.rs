@@ -0,0 +1,335 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde_json::{self, Error as ParserError, Value};
+use duration::as_millis;
+use gzip::crc32;
+use keys::DEFAULT_SNAPSHOT_MAX_AGE_SECS;
+use super::ApplicationsResponse;
+
+/// The envelope version written by [PersistedSnapshot::write](struct.PersistedSnapshot.html#method.write)
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+const FORMAT_VERSION: &'static str = "formatVersion";
+const CHECKSUM_PREFIX: &'static str = "crc32:";
+
+// The first line of a persisted snapshot, the registry follows on the next line. Version 1
+// envelopes have no counts.
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    #[serde(rename = "formatVersion")]
+    format_version: u32,
+    #[serde(rename = "capturedAt")]
+    captured_at: u64,
+    source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    applications: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    instances: Option<usize>,
+    checksum: String
+}
+
+/// Which persisted snapshots are recent enough to load
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotPolicy {
+    /// Snapshots captured longer ago than this are refused
+    pub max_age: Duration,
+    /// Loads snapshots however old they are, for when a stale registry beats none at all
+    pub ignore_age: bool
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        SnapshotPolicy {
+            max_age: Duration::from_secs(DEFAULT_SNAPSHOT_MAX_AGE_SECS),
+            ignore_age: false
+        }
+    }
+}
+
+/// Why a persisted snapshot was not loaded
+#[derive(Debug)]
+pub enum PersistedSnapshotError {
+    Io(io::Error),
+    /// The envelope could not be read, such as a file cut off before its header ends
+    Malformed(String),
+    /// The snapshot was written in a format this version does not know
+    UnsupportedVersion { version: u64 },
+    /// The registry does not match the checksum it was written with
+    ChecksumMismatch { expected: u32, actual: u32 },
+    TooOld { age: Duration, max_age: Duration },
+    /// The checksum matched but the registry is not valid
+    Json(ParserError)
+}
+
+impl Error for PersistedSnapshotError {
+    fn description(&self) -> &str {
+        match *self {
+            PersistedSnapshotError::Io(_) => "Failed to read the persisted snapshot",
+            PersistedSnapshotError::Malformed(_) => "The persisted snapshot is malformed",
+            PersistedSnapshotError::UnsupportedVersion { .. } => "The persisted snapshot has an unsupported format version",
+            PersistedSnapshotError::ChecksumMismatch { .. } => "The persisted snapshot does not match its checksum",
+            PersistedSnapshotError::TooOld { .. } => "The persisted snapshot is too old",
+            PersistedSnapshotError::Json(_) => "The persisted snapshot holds an invalid registry"
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            PersistedSnapshotError::Io(ref error) => Some(error as &Error),
+            PersistedSnapshotError::Json(ref error) => Some(error as &Error),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for PersistedSnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PersistedSnapshotError::Io(ref error) => write!(f, "{}: {}", self.description(), error),
+            PersistedSnapshotError::Malformed(ref reason) => write!(f, "{}: {}", self.description(), reason),
+            PersistedSnapshotError::UnsupportedVersion { version } =>
+                write!(f, "{}: version {}, up to {} is supported", self.description(), version, SNAPSHOT_FORMAT_VERSION),
+            PersistedSnapshotError::ChecksumMismatch { expected, actual } =>
+                write!(f, "{}: expected {:08x}, got {:08x}", self.description(), expected, actual),
+            PersistedSnapshotError::TooOld { age, max_age } =>
+                write!(f, "{}: captured {}s ago, at most {}s are allowed", self.description(), age.as_secs(), max_age.as_secs()),
+            PersistedSnapshotError::Json(ref error) => write!(f, "{}: {}", self.description(), error)
+        }
+    }
+}
+
+/// A registry persisted to disk, to bootstrap from when eureka can not be reached
+///
+/// The file is a header line followed by the registry as returned by `GET /v2/apps`. The
+/// header holds the format version, when and from where the registry was fetched, how many
+/// applications and instances it has and a CRC32 of the registry, so a truncated or edited
+/// file is refused rather than loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistedSnapshot {
+    /// The format version the snapshot was read in, older versions are migrated on reading
+    pub format_version: u32,
+    pub captured_at: SystemTime,
+    /// The eureka server the registry was fetched from
+    pub source: String,
+    pub applications: usize,
+    pub instances: usize,
+    pub registry: ApplicationsResponse
+}
+
+impl PersistedSnapshot {
+    pub fn new(registry: ApplicationsResponse, source: &str, captured_at: SystemTime) -> PersistedSnapshot {
+        let (applications, instances) = counts(&registry);
+        PersistedSnapshot {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            captured_at: captured_at,
+            source: source.to_owned(),
+            applications: applications,
+            instances: instances,
+            registry: registry
+        }
+    }
+
+    /// Writes the snapshot in the current format version
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let payload = serde_json::to_vec(&self.registry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let header = Header {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            captured_at: millis_since_epoch(self.captured_at),
+            source: self.source.clone(),
+            applications: Some(self.applications),
+            instances: Some(self.instances),
+            checksum: format!("{}{:08x}", CHECKSUM_PREFIX, crc32(&payload))
+        };
+        let header = serde_json::to_vec(&header).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        writer.write_all(&header)?;
+        writer.write_all(b"\n")?;
+        writer.write_all(&payload)?;
+        writer.flush()
+    }
+
+    /// Reads a snapshot, refusing it unless it matches its checksum and is recent enough as of `now`
+    ///
+    /// Version 1 snapshots, written before the counts were added to the header, are migrated.
+    /// Versions newer than [SNAPSHOT_FORMAT_VERSION](constant.SNAPSHOT_FORMAT_VERSION.html)
+    /// are refused, they may not mean what this version would read them as.
+    pub fn read<R: Read>(mut reader: R, policy: &SnapshotPolicy, now: SystemTime) -> Result<PersistedSnapshot, PersistedSnapshotError> {
+        let mut contents = Vec::new();
+        reader.read_to_end(&mut contents).map_err(PersistedSnapshotError::Io)?;
+        let split = contents.iter().position(|&b| b == b'\n')
+            .ok_or_else(|| malformed("the header line is not terminated"))?;
+        let (header, payload) = (&contents[..split], &contents[split + 1..]);
+
+        // the version is checked before the rest of the header, a newer header may not parse
+        let header: Value = serde_json::from_slice(header).map_err(|e| malformed(&format!("the header is not valid json: {}", e)))?;
+        let version = header.get(FORMAT_VERSION).and_then(Value::as_u64)
+            .ok_or_else(|| malformed("the header has no formatVersion"))?;
+        if version == 0 || version > u64::from(SNAPSHOT_FORMAT_VERSION) {
+            return Err(PersistedSnapshotError::UnsupportedVersion { version: version });
+        }
+        let header: Header = serde_json::from_value(header).map_err(|e| malformed(&format!("invalid header: {}", e)))?;
+
+        let expected = parse_checksum(&header.checksum)?;
+        let actual = crc32(payload);
+        if expected != actual {
+            return Err(PersistedSnapshotError::ChecksumMismatch { expected: expected, actual: actual });
+        }
+
+        let captured_at = UNIX_EPOCH + Duration::from_millis(header.captured_at);
+        // a snapshot from a clock ahead of ours counts as just captured
+        let age = now.duration_since(captured_at).unwrap_or_else(|_| Duration::from_secs(0));
+        if !policy.ignore_age && age > policy.max_age {
+            return Err(PersistedSnapshotError::TooOld { age: age, max_age: policy.max_age });
+        }
+
+        let registry: ApplicationsResponse = serde_json::from_slice(payload).map_err(PersistedSnapshotError::Json)?;
+        let (applications, instances) = counts(&registry);
+        match (header.format_version, header.applications, header.instances) {
+            (1, _, _) => debug!("Migrating a version 1 snapshot from {}", header.source),
+            (_, Some(a), Some(i)) if (a, i) == (applications, instances) => (),
+            (_, Some(a), Some(i)) => return Err(malformed(&format!("the header counts {} applications and {} instances, the registry has {} and {}",
+                                                                   a, i, applications, instances))),
+            _ => return Err(malformed("the header has no counts"))
+        }
+        Ok(PersistedSnapshot {
+            format_version: header.format_version,
+            captured_at: captured_at,
+            source: header.source,
+            applications: applications,
+            instances: instances,
+            registry: registry
+        })
+    }
+}
+
+fn counts(registry: &ApplicationsResponse) -> (usize, usize) {
+    let applications = &registry.applications.applications;
+    (applications.len(), applications.iter().map(|app| app.instances.len()).sum())
+}
+
+fn parse_checksum(checksum: &str) -> Result<u32, PersistedSnapshotError> {
+    if !checksum.starts_with(CHECKSUM_PREFIX) {
+        return Err(malformed(&format!("unknown checksum {}", checksum)));
+    }
+    u32::from_str_radix(&checksum[CHECKSUM_PREFIX.len()..], 16).map_err(|_| malformed(&format!("invalid checksum {}", checksum)))
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    as_millis(time.duration_since(UNIX_EPOCH).unwrap_or_else(|_| Duration::from_secs(0)))
+}
+
+fn malformed(reason: &str) -> PersistedSnapshotError {
+    PersistedSnapshotError::Malformed(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::applications::tests::build_test_applications;
+
+    const SOURCE: &'static str = "http://eureka:8761/eureka";
+
+    fn captured_at() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1503701416)
+    }
+
+    fn written() -> Vec<u8> {
+        let mut file = Vec::new();
+        PersistedSnapshot::new(ApplicationsResponse::new(build_test_applications()), SOURCE, captured_at())
+            .write(&mut file)
+            .unwrap();
+        file
+    }
+
+    fn read(file: &[u8], after: Duration) -> Result<PersistedSnapshot, PersistedSnapshotError> {
+        PersistedSnapshot::read(file, &SnapshotPolicy::default(), captured_at() + after)
+    }
+
+    // Rewrites the header line of a written snapshot
+    fn with_header<F: FnOnce(&mut Value)>(file: &[u8], f: F) -> Vec<u8> {
+        let split = file.iter().position(|&b| b == b'\n').unwrap();
+        let mut header: Value = serde_json::from_slice(&file[..split]).unwrap();
+        f(&mut header);
+        let mut rewritten = serde_json::to_vec(&header).unwrap();
+        rewritten.extend_from_slice(&file[split..]);
+        rewritten
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let snapshot = read(&written(), Duration::from_secs(60)).unwrap();
+        assert_eq!(PersistedSnapshot::new(ApplicationsResponse::new(build_test_applications()), SOURCE, captured_at()), snapshot);
+        assert_eq!(SNAPSHOT_FORMAT_VERSION, snapshot.format_version);
+    }
+
+    #[test]
+    fn test_corrupt_snapshots_are_refused() {
+        let file = written();
+        match read(&file[..file.len() - 10], Duration::from_secs(60)) {
+            Err(PersistedSnapshotError::ChecksumMismatch { .. }) => (),
+            other => panic!("expected ChecksumMismatch, got {:?}", other)
+        }
+        let mut edited = file.clone();
+        let last = edited.len() - 2;
+        edited[last] = b' ';
+        match read(&edited, Duration::from_secs(60)) {
+            Err(PersistedSnapshotError::ChecksumMismatch { .. }) => (),
+            other => panic!("expected ChecksumMismatch, got {:?}", other)
+        }
+        let split = file.iter().position(|&b| b == b'\n').unwrap();
+        match read(&file[..split / 2], Duration::from_secs(60)) {
+            Err(PersistedSnapshotError::Malformed(_)) => (),
+            other => panic!("expected Malformed, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_wrong_counts_are_refused() {
+        let file = with_header(&written(), |header| header["instances"] = 7.into());
+        match read(&file, Duration::from_secs(60)) {
+            Err(PersistedSnapshotError::Malformed(ref reason)) => assert!(reason.contains("7 instances"), "{}", reason),
+            other => panic!("expected Malformed, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_old_snapshots_are_refused_unless_ignored() {
+        let file = written();
+        let max_age = SnapshotPolicy::default().max_age;
+        assert!(read(&file, max_age).is_ok());
+        match read(&file, max_age + Duration::from_secs(1)) {
+            Err(PersistedSnapshotError::TooOld { age, .. }) => assert_eq!(max_age + Duration::from_secs(1), age),
+            other => panic!("expected TooOld, got {:?}", other)
+        }
+        let ignoring = SnapshotPolicy { ignore_age: true, ..SnapshotPolicy::default() };
+        assert!(PersistedSnapshot::read(&file[..], &ignoring, captured_at() + Duration::from_secs(86400 * 365)).is_ok());
+        // captured by a clock running ahead
+        assert!(PersistedSnapshot::read(&file[..], &SnapshotPolicy::default(), captured_at() - Duration::from_secs(60)).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_versions_are_refused() {
+        for &version in &[0u64, u64::from(SNAPSHOT_FORMAT_VERSION) + 1] {
+            let file = with_header(&written(), |header| *header = serde_json::from_str(&format!(r#"{{"formatVersion":{},"layout":"unknown"}}"#, version)).unwrap());
+            match read(&file, Duration::from_secs(60)) {
+                Err(PersistedSnapshotError::UnsupportedVersion { version: v }) => assert_eq!(version, v),
+                other => panic!("expected UnsupportedVersion, got {:?}", other)
+            }
+        }
+    }
+
+    #[test]
+    fn test_version_1_is_migrated() {
+        let file = with_header(&written(), |header| {
+            let header = header.as_object_mut().unwrap();
+            header.insert("formatVersion".to_owned(), 1.into());
+            header.remove("applications");
+            header.remove("instances");
+        });
+        let snapshot = read(&file, Duration::from_secs(60)).unwrap();
+        assert_eq!(1, snapshot.format_version);
+        assert_eq!(PersistedSnapshot::new(ApplicationsResponse::new(build_test_applications()), SOURCE, captured_at()).instances,
+                   snapshot.instances);
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use super::{ApplicationResponse, ApplicationsResponse, Instance};
+
+/// The identity of an instance in the registry
+///
+/// Eureka stores application names uppercased, so the app is uppercased here too. Instance ids
+/// are compared as they are, eureka treats them case-sensitively.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InstanceKey {
+    pub app: String,
+    pub instance_id: String
+}
+
+impl InstanceKey {
+    pub fn new(app: &str, instance_id: &str) -> InstanceKey {
+        InstanceKey {
+            app: app.to_uppercase(),
+            instance_id: instance_id.to_owned()
+        }
+    }
+}
+
+impl Instance {
+    /// The identity of this instance, see [InstanceKey](struct.InstanceKey.html)
+    pub fn key(&self) -> InstanceKey {
+        InstanceKey::new(&self.app, self.id())
+    }
+
+    // Whether the parts of the instance it registers itself differ, the lease, timestamps and
+    // other values eureka keeps up to date are left out
+    fn registration_differs(&self, other: &Instance) -> bool {
+        self.host_name != other.host_name
+            || self.app_group_name != other.app_group_name
+            || self.ip_addr != other.ip_addr
+            || self.vip_address != other.vip_address
+            || self.secure_vip_address != other.secure_vip_address
+            || self.status != other.status
+            || self.port != other.port
+            || self.secure_port != other.secure_port
+            || self.homepage_url != other.homepage_url
+            || self.status_page_url != other.status_page_url
+            || self.health_check_url != other.health_check_url
+            || self.data_center_info != other.data_center_info
+            || self.metadata != other.metadata
+    }
+}
+
+impl ApplicationsResponse {
+    /// The keys of every registered instance
+    pub fn keys(&self) -> HashSet<InstanceKey> {
+        self.instances().map(|instance| instance.key()).collect()
+    }
+
+    fn instances<'b>(&'b self) -> Box<Iterator<Item=&'b Instance> + 'b> {
+        Box::new(self.applications.applications.iter().flat_map(|app| app.instances.iter()))
+    }
+}
+
+impl ApplicationResponse {
+    /// Whether the registered instances differ from those in `baseline`
+    ///
+    /// Instances are matched by [key](struct.Instance.html#method.key). An instance that was
+    /// added or removed, or whose registration changed, such as its status or metadata, is a
+    /// change. Lease renewals, timestamps and the order of instances or of their fields are not.
+    pub fn changed_since(&self, baseline: &ApplicationResponse) -> bool {
+        let current = first_by_key(&self.application.instances);
+        let previous = first_by_key(&baseline.application.instances);
+        current.len() != previous.len() || current.iter().any(|(key, instance)| match previous.get(key) {
+            Some(before) => instance.registration_differs(before),
+            None => true
+        })
+    }
+}
+
+fn first_by_key(instances: &[Instance]) -> HashMap<InstanceKey, &Instance> {
+    let mut by_key = HashMap::new();
+    for instance in instances {
+        by_key.entry(instance.key()).or_insert(instance);
+    }
+    by_key
+}
+
+/// What it takes to get from the registered instances to the desired ones
+#[derive(Debug, PartialEq)]
+pub struct Reconciliation<'a> {
+    /// Desired instances that are not registered
+    pub to_register: Vec<&'a Instance>,
+    /// Registered instances that are not desired
+    pub to_deregister: Vec<&'a Instance>,
+    /// Desired instances registered with different values
+    pub to_update: Vec<&'a Instance>
+}
+
+impl<'a> Reconciliation<'a> {
+    pub fn is_empty(&self) -> bool {
+        self.to_register.is_empty() && self.to_deregister.is_empty() && self.to_update.is_empty()
+    }
+}
+
+/// Compares the desired instances against a registry snapshot
+///
+/// Instances are matched by [key](struct.Instance.html#method.key). The buckets keep the order
+/// of `desired` and of the snapshot. When a key is registered more than once the first instance
+/// in the snapshot is compared.
+pub fn reconcile<'a>(desired: &'a [Instance], actual: &'a ApplicationsResponse) -> Reconciliation<'a> {
+    let mut registered: HashMap<InstanceKey, &Instance> = HashMap::new();
+    for instance in actual.instances() {
+        registered.entry(instance.key()).or_insert(instance);
+    }
+    let wanted: HashSet<InstanceKey> = desired.iter().map(|instance| instance.key()).collect();
+
+    let mut reconciliation = Reconciliation {
+        to_register: Vec::new(),
+        to_deregister: Vec::new(),
+        to_update: Vec::new()
+    };
+    for instance in desired {
+        match registered.get(&instance.key()) {
+            None => reconciliation.to_register.push(instance),
+            Some(current) if instance.registration_differs(current) => reconciliation.to_update.push(instance),
+            Some(_) => ()
+        }
+    }
+    let mut deregistered = HashSet::new();
+    for instance in actual.instances() {
+        let key = instance.key();
+        if !wanted.contains(&key) && deregistered.insert(key) {
+            reconciliation.to_deregister.push(instance);
+        }
+    }
+    reconciliation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Application, Applications, Status};
+    use super::super::instance::tests::build_test_instance;
+
+    fn instance(app: &str, id: &str) -> Instance {
+        let mut instance = build_test_instance();
+        instance.app = app.to_owned();
+        instance.instance_id = Some(id.to_owned());
+        instance
+    }
+
+    fn registry(instances: Vec<Instance>) -> ApplicationsResponse {
+        ApplicationsResponse::new(Applications {
+            versions_delta: 1,
+            apps_hashcode: "UP_3_".to_owned(),
+            applications: vec![Application { name: "ORDERS".to_owned(), instances: instances }]
+        })
+    }
+
+    fn ids(instances: &[&Instance]) -> Vec<String> {
+        instances.iter().map(|i| i.id().to_owned()).collect()
+    }
+
+    #[test]
+    fn test_key_normalizes_the_app() {
+        assert_eq!(instance("ORDERS", "orders01").key(), instance("orders", "orders01").key());
+        assert!(instance("ORDERS", "orders01").key() != instance("ORDERS", "Orders01").key());
+        assert_eq!(InstanceKey { app: "ORDERS".to_owned(), instance_id: "orders01".to_owned() },
+                   instance("Orders", "orders01").key());
+    }
+
+    #[test]
+    fn test_keys() {
+        let response = registry(vec![instance("ORDERS", "a"), instance("ORDERS", "b"), instance("ORDERS", "a")]);
+        let keys = response.keys();
+        assert_eq!(2, keys.len());
+        assert!(keys.contains(&InstanceKey::new("orders", "b")));
+    }
+
+    #[test]
+    fn test_reconcile_buckets() {
+        let mut changed = instance("orders", "changed");
+        changed.status = Status::OutOfService;
+        let mut renewed = instance("orders", "same");
+        renewed.last_updated_timestamp += 30000;
+        let desired = vec![instance("orders", "new"), renewed, changed];
+        let actual = registry(vec![instance("ORDERS", "same"), instance("ORDERS", "changed"), instance("ORDERS", "stale")]);
+
+        let reconciliation = reconcile(&desired, &actual);
+        assert_eq!(vec!["new"], ids(&reconciliation.to_register));
+        assert_eq!(vec!["changed"], ids(&reconciliation.to_update));
+        assert_eq!(vec!["stale"], ids(&reconciliation.to_deregister));
+    }
+
+    #[test]
+    fn test_reconciled_registry_is_empty() {
+        let desired = vec![instance("orders", "a"), instance("orders", "b")];
+        let actual = registry(vec![instance("ORDERS", "b"), instance("ORDERS", "a")]);
+        assert!(reconcile(&desired, &actual).is_empty());
+    }
+
+    #[test]
+    fn test_application_changed_since() {
+        let application = |instances: Vec<Instance>| ApplicationResponse::new(Application { name: "ORDERS".to_owned(), instances: instances });
+        let baseline = application(vec![instance("ORDERS", "a"), instance("ORDERS", "b")]);
+
+        let mut renewed = instance("ORDERS", "a");
+        renewed.last_updated_timestamp += 30000;
+        assert!(!application(vec![instance("ORDERS", "b"), renewed]).changed_since(&baseline));
+
+        let mut down = instance("ORDERS", "b");
+        down.status = Status::Down;
+        assert!(application(vec![instance("ORDERS", "a"), down]).changed_since(&baseline));
+        let mut tagged = instance("ORDERS", "b");
+        tagged.metadata.insert("zone".to_owned(), "zone-1".into());
+        assert!(application(vec![instance("ORDERS", "a"), tagged]).changed_since(&baseline));
+        assert!(application(vec![instance("ORDERS", "a")]).changed_since(&baseline));
+        assert!(application(vec![instance("ORDERS", "a"), instance("ORDERS", "c")]).changed_since(&baseline));
+    }
+
+    #[test]
+    fn test_duplicates_are_deregistered_once() {
+        let actual = registry(vec![instance("ORDERS", "stale"), instance("ORDERS", "stale")]);
+        assert_eq!(1, reconcile(&[], &actual).to_deregister.len());
+    }
+}
@@ -0,0 +1,10 @@
+/// The health of a Eureka server as reported by its health endpoint
+#[derive(Debug, PartialEq)]
+pub struct ServerHealth {
+    /// The http status code returned by the health endpoint
+    pub status_code: u16,
+    /// True when the server answered with a 2xx status
+    pub healthy: bool,
+    /// The raw body returned by the server
+    pub body: String
+}
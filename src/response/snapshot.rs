@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use serde_json::{self, Error as ParserError};
+use super::ApplicationsResponse;
+
+impl ApplicationsResponse {
+    /// Reads a single registry snapshot, as returned by `GET /v2/apps`
+    pub fn from_reader<R: Read>(reader: R) -> Result<ApplicationsResponse, ParserError> {
+        serde_json::from_reader(reader)
+    }
+}
+
+/// A registry snapshot in a [SnapshotReader](struct.SnapshotReader.html) that could not be read
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Reading the line failed, the reader stops after this
+    Io { line: usize, error: io::Error },
+    /// The line is not a valid snapshot, the reader carries on with the next line
+    Json { line: usize, error: ParserError }
+}
+
+impl SnapshotError {
+    /// The line the error occurred on, starting at 1
+    pub fn line(&self) -> usize {
+        match *self {
+            SnapshotError::Io { line, .. } | SnapshotError::Json { line, .. } => line
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn description(&self) -> &str {
+        match *self {
+            SnapshotError::Io { .. } => "Failed to read a registry snapshot",
+            SnapshotError::Json { .. } => "Invalid registry snapshot"
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            SnapshotError::Io { ref error, .. } => Some(error as &Error),
+            SnapshotError::Json { ref error, .. } => Some(error as &Error)
+        }
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SnapshotError::Io { line, ref error } => write!(f, "{} on line {}: {}", self.description(), line, error),
+            SnapshotError::Json { line, ref error } => write!(f, "{} on line {}: {}", self.description(), line, error)
+        }
+    }
+}
+
+/// Reads registry snapshots stored one json document per line, such as the dumps of a
+/// periodic `GET /v2/apps`
+///
+/// Only one line is held in memory at a time. Blank lines are skipped, a malformed line is
+/// reported and reading continues with the next one.
+pub struct SnapshotReader<R> {
+    reader: R,
+    line: usize,
+    buffer: String,
+    done: bool
+}
+
+impl<R: BufRead> SnapshotReader<R> {
+    pub fn new(reader: R) -> SnapshotReader<R> {
+        SnapshotReader {
+            reader: reader,
+            line: 0,
+            buffer: String::new(),
+            done: false
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SnapshotReader<R> {
+    type Item = Result<ApplicationsResponse, SnapshotError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.buffer.clear();
+            self.line += 1;
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => self.done = true,
+                Ok(_) if self.buffer.trim().is_empty() => (),
+                Ok(_) => {
+                    let line = self.line;
+                    return Some(serde_json::from_str(&self.buffer)
+                        .map_err(|error| SnapshotError::Json { line: line, error: error }));
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(SnapshotError::Io { line: self.line, error: error }));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use super::super::applications::tests::{build_test_applications, build_test_applications_json};
+
+    // A single line snapshot
+    fn snapshot_json() -> String {
+        format!("{{\"applications\":{}}}", build_test_applications_json()).replace("\n", "")
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let response = ApplicationsResponse::from_reader(snapshot_json().as_bytes()).unwrap();
+        assert_eq!(ApplicationsResponse::new(build_test_applications()), response);
+    }
+
+    #[test]
+    fn test_lines() {
+        let json = snapshot_json();
+        let input = format!("{}\n\n{{\"applications\":\n{}", json, json);
+        let results: Vec<_> = SnapshotReader::new(Cursor::new(input)).collect();
+        assert_eq!(3, results.len());
+        assert!(results[0].is_ok());
+        assert_eq!(3, results[1].as_ref().unwrap_err().line());
+        assert!(results[2].is_ok());
+    }
+}
@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use futures::future::Loop;
+use hyper::Error as HyperError;
+use tokio_core::reactor::{Handle, Timeout};
+use duration::as_millis;
+use errors::{ErrorCategory, EurekaClientError};
+use metrics::ErrorMetrics;
+use operation::Operation;
+
+const DEFAULT_BASE_DELAY_MILLIS: u64 = 100;
+const DEFAULT_MAX_DELAY_MILLIS: u64 = 5000;
+
+/// How requests that failed to reach eureka are retried
+///
+/// Only queries are retried unless `retry_writes_on_connect_error` is set, they are safe to send
+/// again. A failed attempt is retried after `base_delay`, doubling with every further attempt
+/// up to `max_delay`. With `jitter` each delay is picked at random between half and all of it,
+/// so clients that lost the same server do not come back in lockstep. Only transport failures
+/// are retried, such as a reset connection, a truncated response or a timeout, a server that
+/// answers is not asked again unless `retry_server_errors` is set. The default makes a single
+/// attempt.
+///
+/// A [RetryBudget](struct.RetryBudget.html) caps the retries of each
+/// [ErrorCategory](errors/enum.ErrorCategory.html), so a cluster wide outage does not turn into
+/// a retry storm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of attempts, including the first one
+    pub max_attempts: u32,
+    /// The delay before the second attempt, 100ms by default
+    pub base_delay: Duration,
+    /// The longest delay between two attempts however many there were, 5s by default
+    pub max_delay: Duration,
+    /// Picks each delay at random between half and all of it, on by default
+    pub jitter: bool,
+    /// Also retry writes, such as register and heartbeat, but only when the connection could
+    /// not be established and so nothing was sent
+    pub retry_writes_on_connect_error: bool,
+    /// Also retry queries eureka answered with a 5xx that may pass, such as a 503
+    pub retry_server_errors: bool,
+    /// The retries allowed for each category of errors, by default they are not limited
+    pub budget: Option<RetryBudget>
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            base_delay: Duration::from_millis(DEFAULT_BASE_DELAY_MILLIS),
+            max_delay: Duration::from_millis(DEFAULT_MAX_DELAY_MILLIS),
+            jitter: true,
+            retry_writes_on_connect_error: false,
+            retry_server_errors: false,
+            budget: None
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Whether a request that failed with `error` on attempt `attempt`, counting from 1, may be
+    // sent again, the budget aside
+    pub(crate) fn should_retry(&self, attempt: u32, idempotent: bool, error: &EurekaClientError) -> bool {
+        attempt < self.max_attempts && self.retries(idempotent, error)
+    }
+
+    // Whether the policy retries this kind of failure at all
+    fn retries(&self, idempotent: bool, error: &EurekaClientError) -> bool {
+        match error.category() {
+            ErrorCategory::Network => idempotent || (self.retry_writes_on_connect_error && error.is_connect_error()),
+            ErrorCategory::Server => self.retry_server_errors && idempotent && error.is_retryable(),
+            ErrorCategory::Client | ErrorCategory::Internal => false
+        }
+    }
+
+    // How long to wait after attempt `attempt` failed
+    #[allow(clippy::legacy_numeric_constants)]
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::max_value());
+        let delay = self.base_delay.checked_mul(factor).map(|delay| cmp::min(delay, self.max_delay))
+            .unwrap_or(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let millis = as_millis(delay);
+        let half = millis / 2;
+        Duration::from_millis(half + random() % (millis - half + 1))
+    }
+}
+
+/// How many retries of one error category a client makes within a window
+///
+/// The retries are counted over every request of the client and its clones. Once a category
+/// spent its budget, requests failing with an error of that category are not retried until
+/// their oldest retry is older than the window, the other categories are still retried.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryBudget {
+    pub max_retries: u32,
+    pub window: Duration
+}
+
+/// The retries spent of each category, shared by a client and its clones
+#[derive(Debug, Default)]
+pub(crate) struct RetryBudgets {
+    spent: RefCell<HashMap<ErrorCategory, VecDeque<Instant>>>
+}
+
+impl RetryBudgets {
+    // Counts a retry of `category` against `budget` unless it is spent
+    fn spend(&self, budget: &RetryBudget, category: ErrorCategory) -> bool {
+        let now = Instant::now();
+        let mut spent = self.spent.borrow_mut();
+        let retries = spent.entry(category).or_default();
+        while retries.front().map(|&at| now.duration_since(at) >= budget.window).unwrap_or(false) {
+            retries.pop_front();
+        }
+        if retries.len() >= budget.max_retries as usize {
+            return false;
+        }
+        retries.push_back(now);
+        true
+    }
+}
+
+/// What the retries of a request report to and count against
+#[derive(Clone)]
+pub(crate) struct RetryAccounting {
+    pub(crate) budgets: Rc<RetryBudgets>,
+    pub(crate) metrics: Rc<ErrorMetrics>,
+    pub(crate) operation: Operation
+}
+
+impl RetryAccounting {
+    // Counts a retry against the policy's budget, if it has one
+    fn spend(&self, policy: &RetryPolicy, category: ErrorCategory) -> bool {
+        let budget = match policy.budget {
+            Some(ref budget) => budget,
+            None => return true
+        };
+        if self.budgets.spend(budget, category) {
+            return true;
+        }
+        warn!("Not retrying {}, the retry budget of {:?} errors is spent", self.operation, category);
+        self.metrics.retry_budget_exhausted(self.operation, category);
+        false
+    }
+}
+
+// Runs `attempt` until it succeeds or the policy gives up, waiting the policy's delay in between.
+// `answered` tells the error a response stands for, if any. Every failed attempt is reported to
+// the metrics. A response the policy does not retry is passed on as it is, unless it came after
+// failed attempts and so ends them.
+pub(crate) fn retry<T, F, A>(policy: RetryPolicy, accounting: RetryAccounting, idempotent: bool, handle: &Handle,
+                             attempt: F, answered: A) -> Box<Future<Item=T, Error=EurekaClientError>>
+    where T: 'static,
+          F: Fn() -> Box<Future<Item=T, Error=EurekaClientError>> + 'static,
+          A: Fn(&T) -> Option<EurekaClientError> + 'static {
+    let handle = handle.clone();
+    let answered = Rc::new(answered);
+    let result = future::loop_fn(1, move |number| {
+        let (policy, accounting, answered) = (policy.clone(), accounting.clone(), answered.clone());
+        let handle = handle.clone();
+        attempt().then(move |result| -> Box<Future<Item=Loop<T, u32>, Error=EurekaClientError>> {
+            let (error, response) = match result {
+                Ok(response) => match answered(&response) {
+                    Some(error) => (error, Some(response)),
+                    None => return Box::new(future::ok(Loop::Break(response)))
+                },
+                Err(error) => (error, None)
+            };
+            let category = error.category();
+            accounting.metrics.failure(accounting.operation, category);
+            if !policy.should_retry(number, idempotent, &error) || !accounting.spend(&policy, category) {
+                return Box::new(match response {
+                    Some(response) if number == 1 || !policy.retries(idempotent, &error) => future::ok(Loop::Break(response)),
+                    _ if number > 1 => future::err(EurekaClientError::RetriesExhausted { attempts: number, last: Box::new(error) }),
+                    _ => future::err(error)
+                });
+            }
+            let delay = policy.delay(number);
+            warn!("Attempt {} of {} failed, retrying in {:?}: {}", number, policy.max_attempts, delay, error);
+            match Timeout::new(delay, &handle) {
+                Ok(timer) => Box::new(timer.map(move |_| Loop::Continue(number + 1))
+                    .map_err(|e| EurekaClientError::from(HyperError::Io(e)))),
+                Err(e) => Box::new(future::err(EurekaClientError::from(HyperError::Io(e))))
+            }
+        })
+    });
+    Box::new(result)
+}
+
+// every RandomState is seeded differently, which is random enough to spread retries
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use super::*;
+
+    fn policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: max_attempts,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter: false,
+            retry_writes_on_connect_error: false,
+            retry_server_errors: false,
+            budget: None
+        }
+    }
+
+    fn io_error(kind: io::ErrorKind) -> EurekaClientError {
+        EurekaClientError::from(HyperError::Io(io::Error::new(kind, "failed")))
+    }
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let delays: Vec<Duration> = (1..7).map(|attempt| policy(10).delay(attempt)).collect();
+        let expected: Vec<Duration> = [100, 200, 400, 800, 1000, 1000].iter().map(|&ms| Duration::from_millis(ms)).collect();
+        assert_eq!(expected, delays);
+        assert_eq!(Duration::from_millis(1000), policy(10).delay(100));
+    }
+
+    #[test]
+    fn test_jitter_stays_within_half_the_delay() {
+        let jittered = RetryPolicy { jitter: true, ..policy(10) };
+        for _ in 0..100 {
+            let delay = jittered.delay(3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400), "{:?}", delay);
+        }
+    }
+
+    #[test]
+    fn test_only_transport_errors_are_retried() {
+        let policy = policy(3);
+        assert!(policy.should_retry(1, true, &io_error(io::ErrorKind::ConnectionReset)));
+        assert!(policy.should_retry(2, true, &EurekaClientError::TruncatedResponse(10)));
+        assert!(policy.should_retry(1, true, &EurekaClientError::Timeout { elapsed: Duration::from_secs(1), operation: Operation::Query }));
+        assert!(!policy.should_retry(3, true, &io_error(io::ErrorKind::ConnectionReset)));
+        assert!(!policy.should_retry(1, true, &EurekaClientError::NotFound));
+        assert!(!policy.should_retry(1, true, &EurekaClientError::InternalServerError(503)));
+    }
+
+    #[test]
+    fn test_writes_are_only_retried_on_connect_errors_when_enabled() {
+        assert!(!policy(3).should_retry(1, false, &io_error(io::ErrorKind::ConnectionRefused)));
+        let writes = RetryPolicy { retry_writes_on_connect_error: true, ..policy(3) };
+        assert!(writes.should_retry(1, false, &io_error(io::ErrorKind::ConnectionRefused)));
+        assert!(!writes.should_retry(1, false, &io_error(io::ErrorKind::ConnectionReset)));
+    }
+
+    #[test]
+    fn test_server_errors_are_only_retried_when_enabled() {
+        assert!(!policy(3).should_retry(1, true, &EurekaClientError::InternalServerError(503)));
+        let servers = RetryPolicy { retry_server_errors: true, ..policy(3) };
+        assert!(servers.should_retry(1, true, &EurekaClientError::InternalServerError(503)));
+        assert!(!servers.should_retry(1, false, &EurekaClientError::InternalServerError(503)));
+        assert!(!servers.should_retry(1, true, &EurekaClientError::NotFound));
+    }
+
+    #[test]
+    fn test_budgets_are_spent_per_category() {
+        let budget = RetryBudget { max_retries: 2, window: Duration::from_secs(3600) };
+        let budgets = RetryBudgets::default();
+        assert!(budgets.spend(&budget, ErrorCategory::Server));
+        assert!(budgets.spend(&budget, ErrorCategory::Server));
+        assert!(!budgets.spend(&budget, ErrorCategory::Server));
+        assert!(budgets.spend(&budget, ErrorCategory::Network));
+    }
+
+    #[test]
+    fn test_budgets_recover_after_the_window() {
+        let budget = RetryBudget { max_retries: 1, window: Duration::from_millis(50) };
+        let budgets = RetryBudgets::default();
+        assert!(budgets.spend(&budget, ErrorCategory::Network));
+        assert!(!budgets.spend(&budget, ErrorCategory::Network));
+        ::std::thread::sleep(Duration::from_millis(60));
+        assert!(budgets.spend(&budget, ErrorCategory::Network));
+    }
+
+    #[test]
+    fn test_default_makes_one_attempt() {
+        assert!(!RetryPolicy::default().should_retry(1, true, &io_error(io::ErrorKind::ConnectionReset)));
+    }
+}
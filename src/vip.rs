@@ -0,0 +1,47 @@
+// Eureka lets an instance serve several vips by separating them with commas in a single
+// vipAddress, as in "orders,orders-canary".
+
+/// The addresses in a vip address, in order, trimmed and without empty ones
+pub(crate) fn split(vip_address: &str) -> Vec<&str> {
+    vip_address.split(',').map(|vip| vip.trim()).filter(|vip| !vip.is_empty()).collect()
+}
+
+/// The canonical vip address serving all of `vip_addresses`, which may hold several each
+pub(crate) fn join<S: AsRef<str>>(vip_addresses: &[S]) -> String {
+    let vips: Vec<&str> = vip_addresses.iter().flat_map(|vips| split(vips.as_ref())).collect();
+    vips.join(",")
+}
+
+/// Whether one of the addresses in a vip address is `vip`, ignoring case as eureka does
+pub(crate) fn contains(vip_address: &str, vip: &str) -> bool {
+    let vip = vip.trim().to_lowercase();
+    split(vip_address).iter().any(|candidate| candidate.to_lowercase() == vip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(vec!["orders"], split("orders"));
+        assert_eq!(vec!["orders", "orders-canary"], split("orders,orders-canary"));
+        assert_eq!(vec!["orders", "orders-canary"], split(" orders , ,orders-canary,"));
+        assert!(split(" , ").is_empty());
+    }
+
+    #[test]
+    fn test_join_is_canonical() {
+        assert_eq!("orders,orders-canary", join(&["orders", " orders-canary "]));
+        assert_eq!("a,b,c", join(&["a, b", "", "c"]));
+        assert_eq!("", join::<&str>(&[]));
+    }
+
+    #[test]
+    fn test_contains() {
+        assert!(contains("orders, orders-canary", "orders-canary"));
+        assert!(contains("orders,orders-canary", "ORDERS"));
+        assert!(!contains("orders,orders-canary", "orders-can"));
+        assert!(!contains("orders,,", ""));
+    }
+}
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use response::{ApplicationsResponse, InstanceKey, Status};
+
+/// Watches that the instances registered through a client stay in the registry
+///
+/// Attach it with [EurekaClient::set_watchdog](struct.EurekaClient.html#method.set_watchdog)
+/// and instances are expected once they registered successfully and forgotten once they
+/// deregistered. Instances can also be expected by hand. Every `check` against a fresh
+/// registry snapshot reports where the expected instances stand. An instance only counts as
+/// missing after it was absent from `misses_to_alarm` checks in a row, so a single poll that
+/// raced a registration does not alarm.
+#[derive(Debug)]
+pub struct Watchdog {
+    misses_to_alarm: u32,
+    // consecutive checks each expected instance was absent from
+    expected: RefCell<BTreeMap<InstanceKey, u32>>
+}
+
+/// Where the expected instances stand in a registry snapshot, each list ordered by key
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WatchdogReport {
+    /// Registered and not down
+    pub present: Vec<InstanceKey>,
+    /// Registered with the DOWN status
+    pub down: Vec<InstanceKey>,
+    /// Absent, but not yet for long enough to alarm
+    pub unconfirmed: Vec<InstanceKey>,
+    /// Absent from enough checks in a row to alarm
+    pub missing: Vec<InstanceKey>
+}
+
+impl WatchdogReport {
+    pub fn is_alarming(&self) -> bool {
+        !self.missing.is_empty()
+    }
+}
+
+impl Watchdog {
+    /// A watchdog that alarms once an instance was absent from `misses_to_alarm` checks in a row,
+    /// at least one
+    pub fn new(misses_to_alarm: u32) -> Watchdog {
+        Watchdog {
+            misses_to_alarm: if misses_to_alarm == 0 { 1 } else { misses_to_alarm },
+            expected: RefCell::new(BTreeMap::new())
+        }
+    }
+
+    pub fn expect(&self, key: InstanceKey) {
+        self.expected.borrow_mut().entry(key).or_insert(0);
+    }
+
+    pub fn forget(&self, key: &InstanceKey) {
+        self.expected.borrow_mut().remove(key);
+    }
+
+    pub fn expected(&self) -> Vec<InstanceKey> {
+        self.expected.borrow().keys().cloned().collect()
+    }
+
+    /// Checks the expected instances against a registry snapshot
+    pub fn check(&self, registry: &ApplicationsResponse) -> WatchdogReport {
+        let mut statuses: HashMap<InstanceKey, &Status> = HashMap::new();
+        for app in &registry.applications.applications {
+            for instance in &app.instances {
+                statuses.entry(instance.key()).or_insert(&instance.status);
+            }
+        }
+
+        let mut report = WatchdogReport::default();
+        for (key, misses) in self.expected.borrow_mut().iter_mut() {
+            match statuses.get(key) {
+                Some(&&Status::Down) => {
+                    *misses = 0;
+                    report.down.push(key.clone());
+                }
+                Some(_) => {
+                    *misses = 0;
+                    report.present.push(key.clone());
+                }
+                None => {
+                    *misses += 1;
+                    if *misses >= self.misses_to_alarm {
+                        report.missing.push(key.clone());
+                    } else {
+                        report.unconfirmed.push(key.clone());
+                    }
+                }
+            }
+        }
+        if report.is_alarming() {
+            warn!("watchdog: {} expected instances are missing from the registry: {:?}", report.missing.len(), report.missing);
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use response::{Application, Applications, Instance};
+    use response::instance::tests::build_test_instance;
+
+    fn registry(instances: &[(&str, Status)]) -> ApplicationsResponse {
+        let instances: Vec<Instance> = instances.iter()
+            .map(|&(id, ref status)| {
+                let mut instance = build_test_instance();
+                instance.app = "ORDERS".to_owned();
+                instance.instance_id = Some(id.to_owned());
+                instance.status = status.clone();
+                instance
+            })
+            .collect();
+        ApplicationsResponse::new(Applications {
+            versions_delta: 1,
+            apps_hashcode: String::new(),
+            applications: vec![Application { name: "ORDERS".to_owned(), instances: instances }]
+        })
+    }
+
+    fn key(id: &str) -> InstanceKey {
+        InstanceKey::new("orders", id)
+    }
+
+    #[test]
+    fn test_alarms_after_consecutive_misses() {
+        let watchdog = Watchdog::new(2);
+        watchdog.expect(key("a"));
+        watchdog.expect(key("b"));
+
+        let report = watchdog.check(&registry(&[("a", Status::Up), ("b", Status::Down)]));
+        assert_eq!(vec![key("a")], report.present);
+        assert_eq!(vec![key("b")], report.down);
+        assert!(!report.is_alarming());
+
+        let gone = registry(&[("b", Status::Up)]);
+        assert_eq!(vec![key("a")], watchdog.check(&gone).unconfirmed);
+        let report = watchdog.check(&gone);
+        assert_eq!(vec![key("a")], report.missing);
+        assert!(report.is_alarming());
+    }
+
+    #[test]
+    fn test_reappearing_resets_the_count() {
+        let watchdog = Watchdog::new(2);
+        watchdog.expect(key("a"));
+        watchdog.check(&registry(&[]));
+        watchdog.check(&registry(&[("a", Status::Up)]));
+        assert!(!watchdog.check(&registry(&[])).is_alarming());
+    }
+
+    #[test]
+    fn test_forgotten_instances_are_not_checked() {
+        let watchdog = Watchdog::new(1);
+        watchdog.expect(key("a"));
+        watchdog.forget(&key("a"));
+        assert_eq!(WatchdogReport::default(), watchdog.check(&registry(&[])));
+    }
+}
@@ -0,0 +1,521 @@
+// hyper 0.11 panics in the task driving a connection when its body decoder fails, which it
+// does as soon as a connection closes before a sized or chunked body ended. The streams handed
+// to hyper follow the framing of every response and keep such a close from hyper, the request
+// reading the body is told about it instead and decides what the bytes it got are worth.
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::rc::Rc;
+use futures::{Async, Future, Poll, Stream};
+use futures::task::{self, Task};
+use hyper::Body;
+use hyper::error::Error as HyperError;
+use hyper::header::Headers;
+use tokio_core::net::TcpStream;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+// Added to every response head so the request reading it can find its connection, it is
+// removed again before anything else sees the headers. Whatever a server sends under the same
+// name is dropped from the head first, so only the client can name a connection.
+pub(crate) const CONNECTION_HEADER: &'static str = "X-Rust-Eureka-Connection";
+
+// Heads larger than this are not followed, hyper refuses them anyway
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// The open connections of a client, by the id added to their responses
+#[derive(Default)]
+pub(crate) struct Connections {
+    next_id: Cell<u64>,
+    open: RefCell<HashMap<u64, Rc<Connection>>>
+}
+
+impl Connections {
+    pub(crate) fn watch(connections: &Rc<Connections>, stream: TcpStream) -> WatchedStream {
+        let id = connections.next_id.get();
+        connections.next_id.set(id + 1);
+        let connection = Rc::new(Connection::default());
+        connections.open.borrow_mut().insert(id, connection.clone());
+        WatchedStream {
+            stream: stream,
+            id: id,
+            marker: format!("{}: {}\r\n", CONNECTION_HEADER, id).into_bytes(),
+            connections: connections.clone(),
+            connection: connection,
+            framing: Framing::Idle,
+            pending: Vec::new()
+        }
+    }
+
+    /// Kept by a request until it has claimed its response, see [Awaiting](struct.Awaiting.html)
+    pub(crate) fn await_response(connections: &Rc<Connections>) -> Awaiting {
+        Awaiting(connections.clone())
+    }
+
+    /// The connection a response arrived on, removing the header naming it
+    pub(crate) fn take(&self, headers: &mut Headers) -> Option<Claim> {
+        let id = headers.get_raw(CONNECTION_HEADER)
+            .and_then(|raw| raw.one())
+            .and_then(|id| String::from_utf8_lossy(id).parse::<u64>().ok());
+        headers.remove_raw(CONNECTION_HEADER);
+        id.and_then(|id| self.open.borrow().get(&id).cloned()).map(Claim::new)
+    }
+
+    // Wakes the connections held on a cut short body that no request claimed, hyper closes
+    // those whose response was dropped and holds the others again
+    fn wake_unclaimed(&self) {
+        let unclaimed: Vec<Rc<Connection>> = self.open.borrow().values()
+            .filter(|connection| !connection.claimed.get())
+            .cloned()
+            .collect();
+        for connection in unclaimed {
+            connection.wake();
+        }
+    }
+}
+
+/// What a connection and the request reading a body from it know of each other
+#[derive(Default)]
+pub(crate) struct Connection {
+    // how the connection ended while a body was not complete
+    closed: RefCell<Option<(io::ErrorKind, String)>>,
+    // whether a request holds a claim on the connection
+    claimed: Cell<bool>,
+    reader: RefCell<Option<Task>>,
+    transport: RefCell<Option<Task>>
+}
+
+impl Connection {
+    fn close(&self, error: &io::Error) {
+        *self.closed.borrow_mut() = Some((error.kind(), error.to_string()));
+        if let Some(reader) = self.reader.borrow_mut().take() {
+            reader.notify();
+        }
+    }
+
+    // Parks hyper's connection task until the request that claimed the connection lets go of
+    // it, or a request goes away without claiming its response
+    fn hold(&self) {
+        *self.transport.borrow_mut() = Some(task::current());
+    }
+
+    fn wake(&self) {
+        if let Some(transport) = self.transport.borrow_mut().take() {
+            transport.notify();
+        }
+    }
+
+    fn poll_closed(&self) -> Option<io::Error> {
+        if let Some((kind, ref reason)) = *self.closed.borrow() {
+            return Some(io::Error::new(kind, reason.clone()));
+        }
+        *self.reader.borrow_mut() = Some(task::current());
+        None
+    }
+}
+
+/// A request's hold on the connection its response arrived on
+///
+/// Once it is dropped, and the body with it, a held connection is woken so that hyper sees the
+/// body is gone and closes the connection.
+pub(crate) struct Claim(Rc<Connection>);
+
+impl Claim {
+    fn new(connection: Rc<Connection>) -> Claim {
+        connection.claimed.set(true);
+        Claim(connection)
+    }
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        self.0.claimed.set(false);
+        self.0.wake();
+    }
+}
+
+/// Wakes the unclaimed held connections when dropped
+///
+/// A request that goes away before claiming its response, such as one that timed out, never
+/// lets go of the connection the response arrived on, this does it instead.
+pub(crate) struct Awaiting(Rc<Connections>);
+
+impl Drop for Awaiting {
+    fn drop(&mut self) {
+        self.0.wake_unclaimed();
+    }
+}
+
+/// A connection to eureka as hyper reads it
+pub(crate) struct WatchedStream {
+    stream: TcpStream,
+    id: u64,
+    // the header line naming the connection
+    marker: Vec<u8>,
+    connections: Rc<Connections>,
+    connection: Rc<Connection>,
+    framing: Framing,
+    // read from the stream and rewritten, not yet passed on
+    pending: Vec<u8>
+}
+
+impl WatchedStream {
+    fn hold(&mut self) -> io::Result<usize> {
+        self.connection.hold();
+        Err(io::Error::new(io::ErrorKind::WouldBlock, "the body was cut short"))
+    }
+}
+
+impl Read for WatchedStream {
+    // Heads are held back until they are complete, so reading goes on until there is something
+    // to pass on or the stream would block
+    #[allow(clippy::mem_replace_with_default)]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if !self.pending.is_empty() {
+                let n = cmp::min(buf.len(), self.pending.len());
+                buf[..n].copy_from_slice(&self.pending[..n]);
+                self.pending.drain(..n);
+                return Ok(n);
+            }
+            if self.connection.closed.borrow().is_some() {
+                return self.hold();
+            }
+            match self.stream.read(buf) {
+                Ok(0) if self.framing.is_delimited() => {
+                    debug!("The connection closed in a {:?} body", self.framing);
+                    self.connection.close(&io::Error::new(io::ErrorKind::UnexpectedEof, "early eof"));
+                    return self.hold();
+                }
+                Ok(0) => {
+                    // a head cut short goes on as it is, for hyper to reject
+                    if let Framing::Head(ref mut head) = self.framing {
+                        self.pending = mem::replace(head, Vec::new());
+                    }
+                    if self.pending.is_empty() {
+                        return Ok(0);
+                    }
+                }
+                Ok(n) => self.framing.feed(&buf[..n], &self.marker, &mut self.pending),
+                Err(ref e) if e.kind() != io::ErrorKind::WouldBlock && self.framing.in_body() => {
+                    debug!("Reading a {:?} body failed: {}", self.framing, e);
+                    self.connection.close(e);
+                    return self.hold();
+                }
+                Err(e) => return Err(e)
+            }
+        }
+    }
+}
+
+impl AsyncRead for WatchedStream {}
+
+impl Write for WatchedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl AsyncWrite for WatchedStream {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        AsyncWrite::shutdown(&mut self.stream)
+    }
+}
+
+impl Drop for WatchedStream {
+    fn drop(&mut self) {
+        self.connections.open.borrow_mut().remove(&self.id);
+    }
+}
+
+/// Reads a whole body, failing with the error the connection ended with when that was before
+/// the body did, together with the bytes read up to then
+pub(crate) struct ReadBody {
+    body: Body,
+    received: Vec<u8>,
+    claim: Option<Claim>
+}
+
+impl ReadBody {
+    pub(crate) fn new(body: Body, claim: Option<Claim>) -> ReadBody {
+        ReadBody {
+            body: body,
+            received: Vec::new(),
+            claim: claim
+        }
+    }
+}
+
+impl Future for ReadBody {
+    type Item = Vec<u8>;
+    type Error = (HyperError, Vec<u8>);
+
+    #[allow(clippy::mem_replace_with_default)]
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        loop {
+            match self.body.poll() {
+                Ok(Async::Ready(Some(chunk))) => self.received.extend_from_slice(&chunk),
+                Ok(Async::Ready(None)) => return Ok(Async::Ready(mem::replace(&mut self.received, Vec::new()))),
+                Ok(Async::NotReady) => break,
+                Err(e) => return Err((e, mem::replace(&mut self.received, Vec::new())))
+            }
+        }
+        match self.claim.as_ref().and_then(|claim| claim.0.poll_closed()) {
+            Some(error) => Err((HyperError::Io(error), mem::replace(&mut self.received, Vec::new()))),
+            None => Ok(Async::NotReady)
+        }
+    }
+}
+
+// Where a connection is in the responses it carries, only as far as knowing whether a close
+// ends a body. Anything it can not follow is left to hyper.
+#[derive(Debug, Clone, PartialEq)]
+enum Framing {
+    // between responses
+    Idle,
+    // the head read so far
+    Head(Vec<u8>),
+    // the bytes left of a body with a Content-Length
+    Sized(u64),
+    Chunked(Chunk),
+    // a body without a length, ended by the close
+    UntilClose,
+    Lost
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Chunk {
+    // the size read so far
+    Size(u64),
+    // skipping an extension up to the end of the size line
+    Extension(u64),
+    // the bytes left of a chunk
+    Data(u64),
+    // the line break after a chunk
+    DataEnd,
+    // after the last chunk, whether the current trailer line is empty so far
+    Trailers(bool)
+}
+
+impl Framing {
+    // Whether the body in progress needs more bytes than a close gives it
+    #[allow(clippy::match_like_matches_macro)]
+    fn is_delimited(&self) -> bool {
+        match *self {
+            Framing::Sized(_) | Framing::Chunked(_) => true,
+            _ => false
+        }
+    }
+
+    fn in_body(&self) -> bool {
+        self.is_delimited() || *self == Framing::UntilClose
+    }
+
+    // Follows the bytes read and copies them to `out`. A head is held back until it is complete,
+    // then copied with `marker` after its status line.
+    fn feed(&mut self, mut data: &[u8], marker: &[u8], out: &mut Vec<u8>) {
+        while !data.is_empty() {
+            let (next, used) = match mem::replace(self, Framing::Lost) {
+                Framing::Idle => (Framing::Head(Vec::new()), 0),
+                Framing::Head(mut head) => {
+                    head.push(data[0]);
+                    data = &data[1..];
+                    if head.ends_with(b"\r\n\r\n") || head.ends_with(b"\n\n") {
+                        rewrite_head(&head, marker, out);
+                        *self = body_framing(&head);
+                    } else if head.len() > MAX_HEAD_SIZE {
+                        out.extend_from_slice(&head);
+                    } else {
+                        *self = Framing::Head(head);
+                    }
+                    continue;
+                }
+                Framing::Sized(remaining) => {
+                    let used = cmp::min(remaining, data.len() as u64);
+                    let next = if used == remaining { Framing::Idle } else { Framing::Sized(remaining - used) };
+                    (next, used as usize)
+                }
+                Framing::Chunked(Chunk::Data(remaining)) => {
+                    let used = cmp::min(remaining, data.len() as u64);
+                    let next = if used == remaining { Chunk::DataEnd } else { Chunk::Data(remaining - used) };
+                    (Framing::Chunked(next), used as usize)
+                }
+                Framing::Chunked(chunk) => (chunk.step(data[0]), 1),
+                Framing::UntilClose => (Framing::UntilClose, data.len()),
+                Framing::Lost => (Framing::Lost, data.len())
+            };
+            *self = next;
+            out.extend_from_slice(&data[..used]);
+            data = &data[used..];
+        }
+    }
+}
+
+impl Chunk {
+    fn step(self, byte: u8) -> Framing {
+        let next = match (self, byte) {
+            (Chunk::Size(size), _) if byte.is_ascii_hexdigit() => {
+                match size.checked_mul(16) {
+                    Some(size) => Chunk::Size(size + (byte as char).to_digit(16).unwrap() as u64),
+                    None => return Framing::Lost
+                }
+            }
+            (Chunk::Size(0), b'\n') | (Chunk::Extension(0), b'\n') => Chunk::Trailers(true),
+            (Chunk::Size(size), b'\n') | (Chunk::Extension(size), b'\n') => Chunk::Data(size),
+            (Chunk::Size(size), _) | (Chunk::Extension(size), _) => Chunk::Extension(size),
+            (Chunk::DataEnd, b'\n') => Chunk::Size(0),
+            (Chunk::DataEnd, _) => Chunk::DataEnd,
+            (Chunk::Trailers(true), b'\n') => return Framing::Idle,
+            (Chunk::Trailers(_), b'\n') => Chunk::Trailers(true),
+            (Chunk::Trailers(empty), b'\r') => Chunk::Trailers(empty),
+            (Chunk::Trailers(_), _) => Chunk::Trailers(false),
+            (Chunk::Data(_), _) => unreachable!("chunk data is taken in bulk")
+        };
+        Framing::Chunked(next)
+    }
+}
+
+// Copies a complete head with `marker` after its status line, leaving out every header the
+// server sent under the marker's name along with its continuation lines
+fn rewrite_head(head: &[u8], marker: &[u8], out: &mut Vec<u8>) {
+    let mut start = 0;
+    let mut dropping = false;
+    while start < head.len() {
+        let end = head[start..].iter().position(|&b| b == b'\n').map(|at| start + at + 1).unwrap_or(head.len());
+        let line = &head[start..end];
+        if start == 0 {
+            out.extend_from_slice(line);
+            out.extend_from_slice(marker);
+        } else {
+            let continued = line.first().map(|&b| b == b' ' || b == b'\t').unwrap_or(false);
+            if !continued {
+                dropping = line.iter().position(|&b| b == b':')
+                    .map(|at| String::from_utf8_lossy(&line[..at]).trim().eq_ignore_ascii_case(CONNECTION_HEADER))
+                    .unwrap_or(false);
+            }
+            if !dropping {
+                out.extend_from_slice(line);
+            }
+        }
+        start = end;
+    }
+}
+
+// How the body after a complete head is delimited, eureka is never sent HEAD requests
+fn body_framing(head: &[u8]) -> Framing {
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.lines();
+    let status = lines.next()
+        .and_then(|status_line| status_line.split_whitespace().nth(1))
+        .and_then(|status| status.parse::<u16>().ok());
+    let status = match status {
+        Some(status) => status,
+        None => return Framing::Lost
+    };
+    if status == 101 {
+        return Framing::Lost;
+    }
+    if status < 200 || status == 204 || status == 304 {
+        return Framing::Idle;
+    }
+    let mut length = None;
+    let mut chunked = false;
+    for line in lines {
+        let mut parts = line.splitn(2, ':');
+        let (name, value) = match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => (name.trim().to_lowercase(), value.trim()),
+            _ => continue
+        };
+        if name == "transfer-encoding" {
+            chunked = value.rsplit(',').next().map(|coding| coding.trim().eq_ignore_ascii_case("chunked")).unwrap_or(false);
+        } else if name == "content-length" {
+            match value.parse::<u64>() {
+                Ok(value) => length = Some(value),
+                Err(_) => return Framing::Lost
+            }
+        }
+    }
+    match (chunked, length) {
+        (true, _) => Framing::Chunked(Chunk::Size(0)),
+        (false, Some(0)) => Framing::Idle,
+        (false, Some(length)) => Framing::Sized(length),
+        (false, None) => Framing::UntilClose
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framing(bytes: &[&str]) -> Framing {
+        let mut framing = Framing::Idle;
+        for bytes in bytes {
+            framing.feed(bytes.as_bytes(), b"", &mut Vec::new());
+        }
+        framing
+    }
+
+    fn fed(framing: &mut Framing, bytes: &str) -> String {
+        let mut out = Vec::new();
+        framing.feed(bytes.as_bytes(), b"Marker: 1\r\n", &mut out);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_heads_are_marked() {
+        let mut framing = Framing::Idle;
+        assert_eq!("", fed(&mut framing, "HTTP/1.1 200"));
+        assert_eq!("HTTP/1.1 200 OK\r\nMarker: 1\r\nContent-Length: 2\r\n\r\n{}",
+                   fed(&mut framing, " OK\r\nContent-Length: 2\r\n\r\n{}"));
+        assert_eq!(Framing::Idle, framing);
+        assert_eq!("HTTP/1.1 200 OK\r\nMarker: 1\r\nContent-Length: 2\r\n\r\n{",
+                   fed(&mut framing, "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{"));
+        assert_eq!(Framing::Sized(1), framing);
+        assert_eq!("}", fed(&mut framing, "}"));
+    }
+
+    #[test]
+    fn test_forged_markers_are_dropped() {
+        let mut framing = Framing::Idle;
+        let head = format!("HTTP/1.1 200 OK\r\n{}: 0\r\nx-rust-eureka-connection :1,\r\n 2\r\nContent-Length: 0\r\n\r\n",
+                           CONNECTION_HEADER);
+        assert_eq!("HTTP/1.1 200 OK\r\nMarker: 1\r\nContent-Length: 0\r\n\r\n", fed(&mut framing, &head));
+    }
+
+    #[test]
+    fn test_chunked_bodies() {
+        let head = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert_eq!(Framing::Chunked(Chunk::Data(1)), framing(&[head, "5\r\n{\"a\""]));
+        assert_eq!(Framing::Chunked(Chunk::Size(0)), framing(&[head, "A;ext=1\r\n0123456789\r\n"]));
+        assert_eq!(Framing::Chunked(Chunk::Trailers(true)), framing(&[head, "2\r\n{}\r\n0\r\n"]));
+        assert_eq!(Framing::Idle, framing(&[head, "2\r\n{}", "\r\n0\r\nExpires: 0\r\n\r\n"]));
+        assert_eq!(Framing::Lost, framing(&[head, "fffffffffffffffff\r\n"]));
+    }
+
+    #[test]
+    fn test_body_framing() {
+        assert_eq!(Framing::Sized(10), framing(&["HTTP/1.1 200 OK\r\ncontent-length: 10\r\n\r\n"]));
+        assert_eq!(Framing::Idle, framing(&["HTTP/1.1 204 No Content\r\n\r\n"]));
+        assert_eq!(Framing::Idle, framing(&["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n"]));
+        assert_eq!(Framing::UntilClose, framing(&["HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n{}"]));
+        assert_eq!(Framing::Chunked(Chunk::Size(0)),
+                   framing(&["HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\nContent-Length: 5\r\n\r\n"]));
+        assert_eq!(Framing::Lost, framing(&["HTTP/1.1 200 OK\r\nContent-Length: ten\r\n\r\n"]));
+        assert_eq!(Framing::Lost, framing(&["garbage\r\n\r\n"]));
+    }
+
+    #[test]
+    fn test_in_body() {
+        assert!(Framing::Sized(1).is_delimited());
+        assert!(Framing::Chunked(Chunk::DataEnd).is_delimited());
+        assert!(!Framing::UntilClose.is_delimited());
+        assert!(Framing::UntilClose.in_body());
+        assert!(!Framing::Idle.in_body());
+        assert!(!Framing::Head(Vec::new()).in_body());
+    }
+}
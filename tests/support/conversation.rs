@@ -0,0 +1,142 @@
+// The exact requests a stub server received, for asserting on the whole exchange rather than
+// on the outcome alone.
+//
+// A conversation renders one line per request:
+//
+//     1 PUT /v2/apps/ORDERS/orders01?status=UP Authorization=Bearer token1 body=-
+//
+// Snapshots are checked in under tests/fixtures/conversations, one expected line per request.
+// `*` in an expected line matches any run of characters, use it for timestamps and ids.
+// Lines starting with `#` and blank lines are ignored.
+
+use std::path::Path;
+use std::fs::File;
+use std::io::Read;
+use serde_json::{Map, Value};
+use super::RecordedRequest;
+
+const WILDCARD: char = '*';
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    pub sequence: usize,
+    pub method: String,
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    /// FNV-1a of the body, `None` for an empty body
+    pub body_hash: Option<u64>
+}
+
+impl Exchange {
+    pub fn render(&self) -> String {
+        let mut line = format!("{} {} {}", self.sequence, self.method, self.uri);
+        for &(ref name, ref value) in &self.headers {
+            line.push_str(&format!(" {}={}", name, value));
+        }
+        match self.body_hash {
+            Some(hash) => line.push_str(&format!(" body={:016x}", hash)),
+            None => line.push_str(" body=-")
+        }
+        line
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mut headers = Map::new();
+        for &(ref name, ref value) in &self.headers {
+            headers.insert(name.clone(), Value::String(value.clone()));
+        }
+        let mut json = Map::new();
+        json.insert("sequence".to_owned(), Value::from(self.sequence as u64));
+        json.insert("method".to_owned(), Value::String(self.method.clone()));
+        json.insert("uri".to_owned(), Value::String(self.uri.clone()));
+        json.insert("headers".to_owned(), Value::Object(headers));
+        json.insert("bodyHash".to_owned(), self.body_hash.map(|h| Value::String(format!("{:016x}", h))).unwrap_or(Value::Null));
+        Value::Object(json)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conversation {
+    pub exchanges: Vec<Exchange>
+}
+
+impl Conversation {
+    /// Records the requests in the order they arrived, keeping only the named headers
+    pub fn new(requests: &[RecordedRequest], headers: &[&str]) -> Conversation {
+        let exchanges = requests.iter().enumerate()
+            .map(|(i, req)| Exchange {
+                sequence: i + 1,
+                method: req.method.clone(),
+                uri: req.uri(),
+                headers: headers.iter()
+                    .filter_map(|name| req.header(name).map(|value| (name.to_string(), value.to_owned())))
+                    .collect(),
+                body_hash: if req.body.is_empty() { None } else { Some(fnv1a(req.body.as_bytes())) }
+            })
+            .collect();
+        Conversation { exchanges: exchanges }
+    }
+
+    pub fn render(&self) -> Vec<String> {
+        self.exchanges.iter().map(|e| e.render()).collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        Value::Array(self.exchanges.iter().map(|e| e.to_json()).collect())
+    }
+
+    /// Panics unless the conversation matches the snapshot in tests/fixtures/conversations
+    pub fn assert_matches(&self, snapshot: &str) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/conversations").join(snapshot);
+        let mut expected = String::new();
+        File::open(&path).and_then(|mut f| f.read_to_string(&mut expected))
+            .unwrap_or_else(|e| panic!("reading {}: {}", path.display(), e));
+        self.assert_matches_str(&expected);
+    }
+
+    pub fn assert_matches_str(&self, expected: &str) {
+        let expected: Vec<&str> = expected.lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+        let actual = self.render();
+        let matches = expected.len() == actual.len()
+            && expected.iter().zip(actual.iter()).all(|(e, a)| wildcard_match(e, a));
+        assert!(matches, "conversation differs\nexpected:\n  {}\nactual:\n  {}",
+                expected.join("\n  "), actual.join("\n  "));
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split(WILDCARD).collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let first = parts[0];
+    let last = parts[parts.len() - 1];
+    if !text.starts_with(first) || text.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &text[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x100000001b3))
+}
+
+#[test]
+fn test_wildcard_match() {
+    assert!(wildcard_match("1 PUT /a", "1 PUT /a"));
+    assert!(!wildcard_match("1 PUT /a", "1 PUT /ab"));
+    assert!(wildcard_match("1 PUT /a?ts=*", "1 PUT /a?ts=1503442035714"));
+    assert!(wildcard_match("* PUT *?status=UP&ts=*", "2 PUT /a?status=UP&ts=1"));
+    assert!(!wildcard_match("1 PUT /a*b", "1 PUT /a"));
+    assert!(!wildcard_match("ab*ba", "aba"));
+}
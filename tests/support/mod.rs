@@ -0,0 +1,355 @@
+// A tiny in-process stand-in for a Eureka server.
+// Each request is recorded and answered by a handler closure supplied by the test.
+#![allow(dead_code)]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use futures::{Future, Stream};
+use futures::sync::oneshot;
+use hyper::{self, StatusCode};
+use hyper::header::ContentLength;
+use hyper::server::{Http, Request, Response, Service};
+use rust_eureka::EurekaClient;
+use tokio_core::reactor::Core;
+
+mod conversation;
+
+pub use self::conversation::Conversation;
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub headers: Vec<(String, String)>,
+    pub body: String
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|&&(ref n, _)| n.to_lowercase() == name.to_lowercase())
+            .map(|&(_, ref v)| v.as_ref())
+    }
+
+    pub fn uri(&self) -> String {
+        match self.query {
+            Some(ref query) => format!("{}?{}", self.path, query),
+            None => self.path.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StubResponse {
+    pub status: StatusCode,
+    pub headers: Vec<(String, String)>,
+    pub body: String
+}
+
+impl StubResponse {
+    pub fn new(status: StatusCode) -> StubResponse {
+        StubResponse {
+            status: status,
+            headers: Vec::new(),
+            body: String::new()
+        }
+    }
+
+    pub fn json(status: StatusCode, body: &str) -> StubResponse {
+        StubResponse::new(status)
+            .with_header("Content-Type", "application/json")
+            .with_body(body)
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> StubResponse {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn with_body(mut self, body: &str) -> StubResponse {
+        self.body = body.to_owned();
+        self
+    }
+}
+
+type Handler = Arc<Fn(&RecordedRequest) -> StubResponse + Send + Sync>;
+type Requests = Arc<Mutex<Vec<RecordedRequest>>>;
+
+pub struct StubServer {
+    addr: SocketAddr,
+    requests: Requests,
+    connections: Arc<AtomicUsize>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl StubServer {
+    /// Starts a server on an ephemeral local port answering every request with `handler`
+    pub fn start<F>(handler: F) -> StubServer
+        where F: Fn(&RecordedRequest) -> StubResponse + Send + Sync + 'static {
+        let handler: Handler = Arc::new(handler);
+        let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+        let (addr_tx, addr_rx) = mpsc::channel();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        let thread_requests = requests.clone();
+        let thread_connections = connections.clone();
+        let thread = thread::spawn(move || {
+            let addr = "127.0.0.1:0".parse().unwrap();
+            let server = Http::new()
+                .bind(&addr, move || {
+                    // a service is made for every accepted connection
+                    thread_connections.fetch_add(1, Ordering::SeqCst);
+                    Ok(StubService {
+                        handler: handler.clone(),
+                        requests: thread_requests.clone()
+                    })
+                })
+                .unwrap();
+            addr_tx.send(server.local_addr().unwrap()).unwrap();
+            server.run_until(shutdown_rx.then(|_| Ok(()))).unwrap();
+        });
+
+        StubServer {
+            addr: addr_rx.recv().unwrap(),
+            requests: requests,
+            connections: connections,
+            shutdown: Some(shutdown_tx),
+            thread: Some(thread)
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The number of connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// The requests received so far, keeping only the named headers
+    pub fn conversation(&self, headers: &[&str]) -> Conversation {
+        Conversation::new(&self.requests(), headers)
+    }
+}
+
+impl Drop for StubServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+struct StubService {
+    handler: Handler,
+    requests: Requests
+}
+
+impl Service for StubService {
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = Box<Future<Item=Response, Error=hyper::Error>>;
+
+    fn call(&self, req: Request) -> Self::Future {
+        let handler = self.handler.clone();
+        let requests = self.requests.clone();
+        let method = req.method().to_string();
+        let path = req.path().to_owned();
+        let query = req.query().map(|q| q.to_owned());
+        let headers = req.headers()
+            .iter()
+            .map(|h| (h.name().to_owned(), h.value_string()))
+            .collect();
+
+        let result = req.body().concat2().map(move |body| {
+            let recorded = RecordedRequest {
+                method: method,
+                path: path,
+                query: query,
+                headers: headers,
+                body: String::from_utf8_lossy(&body).into_owned()
+            };
+            let stub = handler(&recorded);
+            requests.lock().unwrap().push(recorded);
+
+            let mut res = Response::new()
+                .with_status(stub.status)
+                .with_header(ContentLength(stub.body.len() as u64));
+            for (name, value) in stub.headers {
+                res.headers_mut().set_raw(name, value);
+            }
+            res.with_body(stub.body)
+        });
+        Box::new(result)
+    }
+}
+
+/// Answers a single connection with the given bytes, exactly as they are, then closes it
+pub struct RawServer {
+    addr: SocketAddr
+}
+
+impl RawServer {
+    pub fn start(response: Vec<u8>) -> RawServer {
+        RawServer::serve(response, None)
+    }
+
+    /// Like `start`, but keeps the connection open for `hold` after the bytes were sent
+    pub fn stalling(response: Vec<u8>, hold: Duration) -> RawServer {
+        RawServer::serve(response, Some(hold))
+    }
+
+    /// Never answers the first `failures` connections, keeping them open, then answers every
+    /// further connection like `start`
+    pub fn failing_first(failures: usize, response: Vec<u8>) -> RawServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut unanswered = Vec::new();
+            for (accepted, stream) in listener.incoming().enumerate() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => return
+                };
+                if !read_head(&mut stream) {
+                    continue;
+                }
+                if accepted < failures {
+                    unanswered.push(stream);
+                } else {
+                    let _ = stream.write_all(&response);
+                }
+            }
+        });
+        RawServer { addr: addr }
+    }
+
+    /// Answers the n-th connection with the n-th response, keeping it open without an answer
+    /// where the response is `None`, and closes every connection past the last response
+    pub fn scripted(responses: Vec<Option<Vec<u8>>>) -> RawServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut unanswered = Vec::new();
+            let mut responses = responses.into_iter();
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => return
+                };
+                if !read_head(&mut stream) {
+                    continue;
+                }
+                match responses.next() {
+                    Some(Some(response)) => { let _ = stream.write_all(&response); }
+                    Some(None) => unanswered.push(stream),
+                    None => ()
+                }
+            }
+        });
+        RawServer { addr: addr }
+    }
+
+    fn serve(response: Vec<u8>, hold: Option<Duration>) -> RawServer {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                if !read_head(&mut stream) {
+                    return;
+                }
+                let _ = stream.write_all(&response);
+                if let Some(hold) = hold {
+                    thread::sleep(hold);
+                }
+            }
+        });
+        RawServer { addr: addr }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+}
+
+// Reads up to the end of the request head, the raw servers only get GETs
+fn read_head(stream: &mut TcpStream) -> bool {
+    let mut head = Vec::new();
+    let mut buf = [0; 1024];
+    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return false,
+            Ok(n) => head.extend_from_slice(&buf[..n])
+        }
+    }
+    true
+}
+
+/// A `GET /v2/apps/{app}` response body with one instance per status
+pub fn application_json(app: &str, statuses: &[&str]) -> String {
+    let instances: Vec<String> = statuses.iter()
+        .enumerate()
+        .map(|(i, status)| instance_json(app, &format!("host{}", i), status))
+        .collect();
+    format!(r#"{{"application":{{"name":"{}","instance":[{}]}}}}"#, app, instances.join(","))
+}
+
+pub fn instance_json(app: &str, host_name: &str, status: &str) -> String {
+    format!(r#"{{
+        "hostName": "{host}",
+        "app": "{app}",
+        "ipAddr": "127.0.0.1",
+        "status": "{status}",
+        "overriddenstatus": "UNKNOWN",
+        "port": {{"$": "7001", "@enabled": "true"}},
+        "securePort": {{"$": "7002", "@enabled": "false"}},
+        "countryId": 1,
+        "dataCenterInfo": {{
+            "@class": "com.netflix.appinfo.InstanceInfo$DefaultDataCenterInfo",
+            "name": "MyOwn"
+        }},
+        "leaseInfo": {{
+            "renewalIntervalInSecs": 30,
+            "durationInSecs": 90,
+            "registrationTimestamp": 1503701416749,
+            "lastRenewalTimestamp": 1503701416749,
+            "evictionTimestamp": 0,
+            "serviceUpTimestamp": 1503701416464
+        }},
+        "metadata": {{"@class": "java.util.Collections$EmptyMap"}},
+        "homePageUrl": "http://{host}:7001/",
+        "statusPageUrl": "http://{host}:7001/info",
+        "healthCheckUrl": "http://{host}:7001/health",
+        "vipAddress": "{vip}",
+        "secureVipAddress": "{vip}",
+        "isCoordinatingDiscoveryServer": false,
+        "lastUpdatedTimestamp": 1503701416750,
+        "lastDirtyTimestamp": 1503701416457,
+        "actionType": "ADDED"
+    }}"#, host = host_name, app = app, status = status, vip = app.to_lowercase())
+}
+
+/// A reactor and a client of it for the server at `url`, the client named like every other test
+/// client. The reactor's handle is leaked so the client can outlive this call.
+pub fn client_for(url: &str) -> (Core, EurekaClient<'static>) {
+    let core = Core::new().unwrap();
+    let handle = Box::leak(Box::new(core.handle()));
+    let client = EurekaClient::new(handle, ::EUREKA_CLIENT, url);
+    (core, client)
+}